@@ -0,0 +1,322 @@
+use std::future::{Future, poll_fn};
+use std::io::{self, Read, Write};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
+use std::pin::{Pin, pin};
+use std::task::Poll;
+use std::time::Duration;
+
+use crate::reactor;
+
+/// Non-blocking wrapper around a pipe or terminal of a [`Process`], usable
+/// from any async runtime
+///
+/// Readiness is reported by a reactor thread built into the crate, so the
+/// futures returned by [`AsyncIo`] can be awaited with tokio, smol,
+/// async-std or a plain `block_on` alike. Works with [`ChildStdin`],
+/// [`ChildStdout`], [`ChildStderr`] and [`Pty`].
+///
+/// **Important:** the file descriptor is switched to non-blocking mode,
+/// which also affects blocking reads through [`AsyncIo::get_mut`].
+///
+/// **Note:** [`AsyncIo::read_line`] and [`AsyncIo::next_line`] buffer the
+/// bytes following the line they return. Later reads through [`AsyncIo`]
+/// return them first, reads through the wrapped object skip them.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut process = Process::builder("ls").stdout(StdioMode::Piped).spawn().expect("Failed to spawn");
+/// let mut stdout = AsyncIo::new(process.take_stdout().unwrap()).expect("Failed to set up stdout");
+/// let mut output = Vec::new();
+/// smol::block_on(stdout.read_to_end(&mut output)).expect("Failed to read stdout");
+/// ```
+///
+/// [`Process`]: crate::Process
+/// [`ChildStdin`]: std::process::ChildStdin
+/// [`ChildStdout`]: std::process::ChildStdout
+/// [`ChildStderr`]: std::process::ChildStderr
+/// [`Pty`]: crate::Pty
+#[derive(Debug)]
+pub struct AsyncIo<T: AsFd> {
+    io: T,
+    /// Bytes read ahead by the line readers and not returned yet
+    buffer: Vec<u8>,
+}
+
+impl<T: AsFd> AsyncIo<T> {
+    /// Wraps `io`, switching its file descriptor to non-blocking mode
+    pub fn new(io: T) -> io::Result<Self> {
+        reactor::set_nonblocking(io.as_fd().as_raw_fd())?;
+        Ok(Self {
+            io,
+            buffer: Vec::new(),
+        })
+    }
+
+    /// Returns a reference to the wrapped I/O object
+    pub fn get_ref(&self) -> &T {
+        &self.io
+    }
+
+    /// Returns a mutable reference to the wrapped I/O object
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.io
+    }
+
+    /// Unwraps the I/O object, which is left in non-blocking mode
+    ///
+    /// Bytes buffered by the line readers are discarded.
+    pub fn into_inner(self) -> T {
+        self.io
+    }
+
+    /// Waits until reading does not block, either because data is available
+    /// or the other end was closed
+    pub async fn readable(&self) -> io::Result<()> {
+        reactor::readable(self.io.as_fd()).await
+    }
+
+    /// Waits until writing does not block
+    pub async fn writable(&self) -> io::Result<()> {
+        reactor::writable(self.io.as_fd()).await
+    }
+}
+
+impl<T: AsFd + Read> AsyncIo<T> {
+    /// Reads into `buf`, returning the number of bytes read or `0` at end of
+    /// file
+    pub async fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.buffer.is_empty() {
+            let len = buf.len().min(self.buffer.len());
+            buf[..len].copy_from_slice(&self.buffer[..len]);
+            self.buffer.drain(..len);
+            return Ok(len);
+        }
+
+        self.read_io(buf).await
+    }
+
+    /// Reads from the wrapped object, bypassing the buffer
+    async fn read_io(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            match self.io.read(buf) {
+                Err(err) if retry(&err) => self.readable().await?,
+                result => return result,
+            }
+        }
+    }
+
+    /// Reads until end of file, appending to `buf`, and returns the number of
+    /// bytes read
+    pub async fn read_to_end(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
+        let start = buf.len();
+        let mut chunk = [0; 8192];
+
+        loop {
+            match self.read(&mut chunk).await? {
+                0 => return Ok(buf.len() - start),
+                len => buf.extend_from_slice(&chunk[..len]),
+            }
+        }
+    }
+
+    /// Reads until a newline, which is included, or end of file and appends
+    /// the line to `buf`
+    ///
+    /// Returns the number of bytes read, `0` at end of file. The future can
+    /// be cancelled, for instance by [`timeout`], without losing data: a
+    /// partial line stays buffered for the next call.
+    pub async fn read_line(&mut self, buf: &mut String) -> io::Result<usize> {
+        let mut searched = 0;
+        let mut chunk = [0; 8192];
+
+        let end = loop {
+            if let Some(pos) = self.buffer[searched..].iter().position(|&b| b == b'\n') {
+                break searched + pos + 1;
+            }
+
+            searched = self.buffer.len();
+
+            match self.read_io(&mut chunk).await? {
+                0 => break self.buffer.len(),
+                len => self.buffer.extend_from_slice(&chunk[..len]),
+            }
+        };
+
+        let line = String::from_utf8(self.buffer.drain(..end).collect())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        buf.push_str(&line);
+
+        Ok(end)
+    }
+
+    /// Reads the next line without its `\n` or `\r\n` terminator, or `None`
+    /// at end of file
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut stdout = AsyncIo::new(process.take_stdout().unwrap()).expect("Failed to set up stdout");
+    /// while let Some(line) = stdout.next_line().await.expect("Failed to read stdout") {
+    ///     println!("{line}");
+    /// }
+    /// ```
+    pub async fn next_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+
+        if self.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+
+        if line.ends_with('\n') {
+            line.pop();
+
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+
+        Ok(Some(line))
+    }
+}
+
+impl<T: AsFd + Write> AsyncIo<T> {
+    /// Writes from `buf`, returning the number of bytes written
+    pub async fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        loop {
+            match self.io.write(buf) {
+                Err(err) if retry(&err) => self.writable().await?,
+                result => return result,
+            }
+        }
+    }
+
+    /// Writes the whole of `buf`
+    pub async fn write_all(&mut self, mut buf: &[u8]) -> io::Result<()> {
+        while !buf.is_empty() {
+            match self.write(buf).await? {
+                0 => return Err(io::ErrorKind::WriteZero.into()),
+                len => buf = &buf[len..],
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: AsFd> AsFd for AsyncIo<T> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.io.as_fd()
+    }
+}
+
+/// Awaits `future` for at most `duration`
+///
+/// Fails with [`io::ErrorKind::TimedOut`] once `duration` elapsed, dropping
+/// `future` and thereby cancelling it. Works with any async runtime.
+///
+/// # Example
+///
+/// ```ignore
+/// match xprocess::timeout(Duration::from_secs(5), process.wait_async()).await {
+///     Ok(status) => println!("Exited with {:?}", status.expect("Failed to wait for process")),
+///     Err(_) => process.kill().expect("Failed to kill process"),
+/// }
+/// ```
+pub async fn timeout<F: Future>(duration: Duration, future: F) -> io::Result<F::Output> {
+    let mut future = pin!(future);
+    let mut sleep = reactor::sleep(duration);
+
+    poll_fn(|cx| {
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            return Poll::Ready(Ok(output));
+        }
+
+        match Pin::new(&mut sleep).poll(cx) {
+            Poll::Ready(_) => Poll::Ready(Err(io::ErrorKind::TimedOut.into())),
+            Poll::Pending => Poll::Pending,
+        }
+    })
+    .await
+}
+
+/// Whether the operation failed only because it would have blocked
+fn retry(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reactor::block_on;
+    use crate::{Process, StdioMode};
+
+    #[test]
+    fn reads_output_asynchronously() {
+        let mut process = Process::builder("sh")
+            .args(["-c", "echo hello; sleep 0.1; echo world"])
+            .stdout(StdioMode::Piped)
+            .spawn()
+            .expect("Failed to spawn process");
+        let mut stdout = AsyncIo::new(process.take_stdout().unwrap()).unwrap();
+
+        let mut output = Vec::new();
+        block_on(stdout.read_to_end(&mut output)).expect("Failed to read stdout");
+        assert_eq!(output, b"hello\nworld\n");
+        assert!(process.wait().unwrap().success());
+    }
+
+    #[test]
+    fn writes_input_asynchronously() {
+        let mut process = Process::builder("cat")
+            .stdin(StdioMode::Piped)
+            .stdout(StdioMode::Piped)
+            .spawn()
+            .expect("Failed to spawn process");
+        let mut stdin = AsyncIo::new(process.take_stdin().unwrap()).unwrap();
+
+        block_on(stdin.write_all(b"ping\n")).expect("Failed to write stdin");
+        drop(stdin);
+        assert_eq!(process.stdout().unwrap(), "ping\n");
+    }
+
+    #[test]
+    fn reads_lines_asynchronously() {
+        let mut process = Process::builder("printf")
+            .arg("one\\ntwo\\r\\n\\nthree")
+            .stdout(StdioMode::Piped)
+            .spawn()
+            .expect("Failed to spawn process");
+        let mut stdout = AsyncIo::new(process.take_stdout().unwrap()).unwrap();
+
+        let mut lines = Vec::new();
+        while let Some(line) = block_on(stdout.next_line()).expect("Failed to read line") {
+            lines.push(line);
+        }
+        assert_eq!(lines, ["one", "two", "", "three"]);
+        assert!(process.wait().unwrap().success());
+    }
+
+    #[test]
+    fn keeps_partial_lines_after_timeout() {
+        let mut process = Process::builder("sh")
+            .args(["-c", "printf par; sleep 0.3; echo tial"])
+            .stdout(StdioMode::Piped)
+            .spawn()
+            .expect("Failed to spawn process");
+        let mut stdout = AsyncIo::new(process.take_stdout().unwrap()).unwrap();
+
+        let err = block_on(timeout(Duration::from_millis(100), stdout.next_line()))
+            .expect_err("Line should not be complete yet");
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+
+        let line = block_on(timeout(Duration::from_secs(5), stdout.next_line()))
+            .expect("Timed out reading line")
+            .expect("Failed to read line");
+        assert_eq!(line.as_deref(), Some("partial"));
+        assert!(process.wait().unwrap().success());
+    }
+}
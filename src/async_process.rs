@@ -0,0 +1,284 @@
+use std::io;
+use std::os::fd::OwnedFd;
+use std::time::Duration;
+
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader, Lines};
+use tokio::net::unix::pipe::{Receiver, Sender};
+
+use crate::{ExitStatus, Output, Process, Result};
+
+/// Tokio counterpart of a [`Process`], with its stdin, stdout and stderr
+/// pipes registered with the tokio reactor
+///
+/// Spawned with [`ProcessBuilder::spawn_async`], which applies every setting
+/// of the builder exactly like [`ProcessBuilder::spawn`], including the new
+/// session or process group of the child. Waiting never blocks a worker
+/// thread, so no `spawn_blocking` is needed.
+///
+/// **Note:** The terminal of processes spawned with
+/// [`ProcessBuilder::pty`] is not converted, wrap it with [`AsyncIo`]
+/// instead.
+///
+/// # Example
+///
+/// ```ignore
+/// use xprocess::{ProcessBuilder, StdioMode};
+///
+/// let mut process = ProcessBuilder::new("journalctl")
+///     .args(["-f"])
+///     .stdout(StdioMode::Piped)
+///     .spawn_async()
+///     .expect("Failed to spawn");
+/// let mut lines = process.stdout_lines().unwrap();
+/// while let Some(line) = lines.next_line().await.expect("Failed to read stdout") {
+///     println!("{line}");
+/// }
+/// ```
+///
+/// [`ProcessBuilder::spawn_async`]: crate::ProcessBuilder::spawn_async
+/// [`ProcessBuilder::spawn`]: crate::ProcessBuilder::spawn
+/// [`ProcessBuilder::pty`]: crate::ProcessBuilder::pty
+/// [`AsyncIo`]: crate::AsyncIo
+#[derive(Debug)]
+pub struct AsyncProcess {
+    process: Process,
+    stdin: Option<Sender>,
+    stdout: Option<Receiver>,
+    stderr: Option<Receiver>,
+}
+
+impl AsyncProcess {
+    /// Registers the pipes of `process` with the tokio reactor
+    ///
+    /// **Important:** Must be called from within a tokio runtime.
+    pub(crate) fn new(mut process: Process) -> Result<Self> {
+        let stdin = process
+            .take_stdin()
+            .map(|stdin| Sender::from_owned_fd(OwnedFd::from(stdin)))
+            .transpose()?;
+        let stdout = process
+            .take_stdout()
+            .map(|stdout| Receiver::from_owned_fd(OwnedFd::from(stdout)))
+            .transpose()?;
+        let stderr = process
+            .take_stderr()
+            .map(|stderr| Receiver::from_owned_fd(OwnedFd::from(stderr)))
+            .transpose()?;
+
+        Ok(Self {
+            process,
+            stdin,
+            stdout,
+            stderr,
+        })
+    }
+
+    /// Retrieves PID for the spawned process
+    pub fn pid(&self) -> u32 {
+        self.process.pid()
+    }
+
+    /// Returns the underlying [`Process`], to signal or inspect it
+    pub fn process(&self) -> &Process {
+        &self.process
+    }
+
+    /// Returns the underlying [`Process`] mutably
+    pub fn process_mut(&mut self) -> &mut Process {
+        &mut self.process
+    }
+
+    /// Retrieves an async writer for the stdin pipe of the process
+    ///
+    /// Returns [`None`] unless stdin was configured with
+    /// [`StdioMode::Piped`] and not fed with
+    /// [`ProcessBuilder::stdin_bytes`].
+    ///
+    /// [`StdioMode::Piped`]: crate::StdioMode::Piped
+    /// [`ProcessBuilder::stdin_bytes`]: crate::ProcessBuilder::stdin_bytes
+    pub fn stdin(&mut self) -> Option<&mut Sender> {
+        self.stdin.as_mut()
+    }
+
+    /// Takes ownership of the stdin pipe of the process
+    pub fn take_stdin(&mut self) -> Option<Sender> {
+        self.stdin.take()
+    }
+
+    /// Closes the stdin pipe of the process, signaling the end of its input
+    pub fn close_stdin(&mut self) {
+        self.stdin = None;
+    }
+
+    /// Takes ownership of the stdout pipe of the process
+    pub fn take_stdout(&mut self) -> Option<Receiver> {
+        self.stdout.take()
+    }
+
+    /// Takes ownership of the stderr pipe of the process
+    pub fn take_stderr(&mut self) -> Option<Receiver> {
+        self.stderr.take()
+    }
+
+    /// Takes the stdout pipe of the process as a stream of lines
+    pub fn stdout_lines(&mut self) -> Option<Lines<BufReader<Receiver>>> {
+        self.take_stdout()
+            .map(|stdout| BufReader::new(stdout).lines())
+    }
+
+    /// Takes the stderr pipe of the process as a stream of lines
+    pub fn stderr_lines(&mut self) -> Option<Lines<BufReader<Receiver>>> {
+        self.take_stderr()
+            .map(|stderr| BufReader::new(stderr).lines())
+    }
+
+    /// Waits for the process to exit and returns its [`ExitStatus`]
+    ///
+    /// The stdin pipe is closed before waiting to prevent the process from
+    /// blocking on input. The future can be dropped at any time, the process
+    /// keeps running and can be waited for again.
+    pub async fn wait(&mut self) -> Result<ExitStatus> {
+        self.close_stdin();
+        self.process.wait_async().await
+    }
+
+    /// Waits for the process to exit for at most `timeout`
+    ///
+    /// Returns [`None`] if the process is still running once `timeout`
+    /// elapsed.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// if process.wait_timeout(Duration::from_secs(5)).await?.is_none() {
+    ///     process.process().kill_group()?;
+    /// }
+    /// ```
+    pub async fn wait_timeout(&mut self, timeout: Duration) -> Result<Option<ExitStatus>> {
+        match tokio::time::timeout(timeout, self.wait()).await {
+            Ok(status) => status.map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Waits for the process to exit while collecting stdout and stderr
+    ///
+    /// Both pipes are read concurrently, so a process filling up one of
+    /// them can not deadlock. Pipes which were taken are left out.
+    pub async fn wait_with_output(&mut self) -> Result<Output> {
+        self.close_stdin();
+        let (stdout, stderr) = tokio::try_join!(
+            read_to_end(self.stdout.take()),
+            read_to_end(self.stderr.take())
+        )?;
+
+        Ok(Output {
+            status: self.wait().await?,
+            usage: self.process.resource_usage(),
+            stdout,
+            stderr,
+        })
+    }
+}
+
+/// Reads `pipe` until end of file
+async fn read_to_end(pipe: Option<Receiver>) -> io::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+
+    if let Some(mut pipe) = pipe {
+        pipe.read_to_end(&mut buf).await?;
+    }
+
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::time::Instant;
+
+    use tokio::io::AsyncWriteExt;
+
+    use super::*;
+    use crate::{ProcessBuilder, StdioMode};
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("Failed to build runtime")
+            .block_on(future)
+    }
+
+    #[test]
+    fn streams_output_lines() {
+        block_on(async {
+            let mut process = ProcessBuilder::new("sh")
+                .args(["-c", "echo one; sleep 0.1; echo two; echo err >&2"])
+                .stdout(StdioMode::Piped)
+                .stderr(StdioMode::Piped)
+                .spawn_async()
+                .expect("Failed to spawn process");
+
+            let mut lines = process.stdout_lines().unwrap();
+            let mut stdout = Vec::new();
+            while let Some(line) = lines.next_line().await.expect("Failed to read stdout") {
+                stdout.push(line);
+            }
+            assert_eq!(stdout, ["one", "two"]);
+
+            let mut lines = process.stderr_lines().unwrap();
+            assert_eq!(lines.next_line().await.unwrap().as_deref(), Some("err"));
+            assert!(process.wait().await.unwrap().success());
+        });
+    }
+
+    #[test]
+    fn writes_stdin_asynchronously() {
+        block_on(async {
+            let mut process = ProcessBuilder::new("cat")
+                .stdin(StdioMode::Piped)
+                .stdout(StdioMode::Piped)
+                .spawn_async()
+                .expect("Failed to spawn process");
+
+            let stdin = process.stdin().expect("Stdin is not piped");
+            stdin.write_all(b"ping\n").await.unwrap();
+            let output = process.wait_with_output().await.unwrap();
+            assert!(output.status.success());
+            assert_eq!(output.stdout, b"ping\n");
+        });
+    }
+
+    #[test]
+    fn times_out_waiting() {
+        block_on(async {
+            let mut process = ProcessBuilder::new("sleep")
+                .arg("10")
+                .spawn_async()
+                .expect("Failed to spawn process");
+
+            let started = Instant::now();
+            let status = process.wait_timeout(Duration::from_millis(100)).await;
+            assert_eq!(status.unwrap(), None);
+            assert!(started.elapsed() < Duration::from_secs(5));
+
+            process.process().kill_group().unwrap();
+            let status = process.wait().await.unwrap();
+            assert_eq!(status.signal_name(), Some("SIGTERM"));
+        });
+    }
+
+    #[test]
+    fn collects_output() {
+        let output = block_on(
+            ProcessBuilder::new("sh")
+                .args(["-c", "echo out; echo err >&2; exit 2"])
+                .output_async(),
+        )
+        .expect("Failed to run process");
+        assert_eq!(output.status.code(), Some(2));
+        assert_eq!(output.stdout, b"out\n");
+        assert_eq!(output.stderr, b"err\n");
+    }
+}
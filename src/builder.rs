@@ -0,0 +1,1073 @@
+use std::ffi::{OsStr, OsString};
+use std::fs::File;
+use std::io;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+#[cfg(feature = "tokio")]
+use crate::AsyncProcess;
+use crate::daemon::DEFAULT_UMASK;
+use crate::exec::PreExec;
+use crate::user::Credentials;
+use crate::{Detach, Group, Output, Process, Pty, Resource, Result, Rlimit, User, WindowSize};
+#[cfg(target_os = "linux")]
+use crate::{IoPriority, SchedPolicy};
+
+/// Configuration for one of the standard streams of a spawned [`Process`]
+#[derive(Debug)]
+pub enum StdioMode {
+    /// Connects the stream to `/dev/null`
+    Null,
+    /// Shares the stream with the parent process
+    Inherit,
+    /// Connects the stream to a pipe owned by the [`Process`]
+    Piped,
+    /// Redirects the stream to (or from) the provided file
+    File(File),
+}
+
+impl StdioMode {
+    /// Builds a [`Stdio`] for this mode.
+    ///
+    /// Files are duplicated so the same [`ProcessBuilder`] can be spawned
+    /// more than once.
+    fn to_stdio(&self) -> io::Result<Stdio> {
+        match self {
+            StdioMode::Null => Ok(Stdio::null()),
+            StdioMode::Inherit => Ok(Stdio::inherit()),
+            StdioMode::Piped => Ok(Stdio::piped()),
+            StdioMode::File(file) => Ok(Stdio::from(file.try_clone()?)),
+        }
+    }
+}
+
+impl From<File> for StdioMode {
+    fn from(file: File) -> Self {
+        StdioMode::File(file)
+    }
+}
+
+/// Builder used to configure how a [`Process`] is spawned
+///
+/// By default stdin is connected to `/dev/null` while stdout and stderr are
+/// piped, matching [`Process::spawn`].
+///
+/// # Example
+///
+/// ```ignore
+/// use xprocess::{ProcessBuilder, StdioMode};
+///
+/// fn main() {
+///     let mut process = ProcessBuilder::new("sh")
+///         .args(["-c", "echo $GREETING"])
+///         .env("GREETING", "hello")
+///         .current_dir("/tmp")
+///         .stderr(StdioMode::Inherit)
+///         .spawn()
+///         .expect("Failed to spawn process");
+///
+///     println!("{}", process.stdout().expect("Failed to read stdout"));
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ProcessBuilder {
+    program: OsString,
+    args: Vec<OsString>,
+    arg0: Option<OsString>,
+    env_clear: bool,
+    /// Variables to set, or to remove when the value is [`None`]
+    envs: Vec<(OsString, Option<OsString>)>,
+    current_dir: Option<PathBuf>,
+    pre_exec: PreExec,
+    user: Option<User>,
+    group: Option<Group>,
+    groups: Option<Vec<Group>>,
+    stdin: StdioMode,
+    stdout: StdioMode,
+    stderr: StdioMode,
+    stdin_data: Option<Vec<u8>>,
+    /// Size of the pseudo-terminal connected to the standard streams
+    pty: Option<WindowSize>,
+    kill_on_drop: bool,
+}
+
+impl ProcessBuilder {
+    /// Creates a new builder for the program at `cmd`
+    pub fn new<S: AsRef<OsStr>>(cmd: S) -> Self {
+        Self {
+            program: cmd.as_ref().to_owned(),
+            args: Vec::new(),
+            arg0: None,
+            env_clear: false,
+            envs: Vec::new(),
+            current_dir: None,
+            pre_exec: PreExec::default(),
+            user: None,
+            group: None,
+            groups: None,
+            stdin: StdioMode::Null,
+            stdout: StdioMode::Piped,
+            stderr: StdioMode::Piped,
+            stdin_data: None,
+            pty: None,
+            kill_on_drop: false,
+        }
+    }
+
+    /// Appends an argument to the program
+    pub fn arg<S: AsRef<OsStr>>(&mut self, arg: S) -> &mut Self {
+        self.args.push(arg.as_ref().to_owned());
+        self
+    }
+
+    /// Appends multiple arguments to the program
+    pub fn args<I, T>(&mut self, args: I) -> &mut Self
+    where
+        T: AsRef<OsStr>,
+        I: IntoIterator<Item = T>,
+    {
+        self.args
+            .extend(args.into_iter().map(|arg| arg.as_ref().to_owned()));
+        self
+    }
+
+    /// Overrides the value of `argv[0]` seen by the program
+    pub fn arg0<S: AsRef<OsStr>>(&mut self, arg0: S) -> &mut Self {
+        self.arg0 = Some(arg0.as_ref().to_owned());
+        self
+    }
+
+    /// Sets an environment variable for the program
+    pub fn env<K, V>(&mut self, key: K, val: V) -> &mut Self
+    where
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        self.envs
+            .push((key.as_ref().to_owned(), Some(val.as_ref().to_owned())));
+        self
+    }
+
+    /// Sets multiple environment variables for the program
+    pub fn envs<I, K, V>(&mut self, vars: I) -> &mut Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        for (key, val) in vars {
+            self.env(key, val);
+        }
+        self
+    }
+
+    /// Removes an environment variable inherited from the parent process
+    pub fn env_remove<K: AsRef<OsStr>>(&mut self, key: K) -> &mut Self {
+        self.envs.push((key.as_ref().to_owned(), None));
+        self
+    }
+
+    /// Clears every environment variable, including the ones inherited from
+    /// the parent process
+    pub fn env_clear(&mut self) -> &mut Self {
+        self.env_clear = true;
+        self.envs.clear();
+        self
+    }
+
+    /// Sets the working directory for the program
+    pub fn current_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Self {
+        self.current_dir = Some(dir.as_ref().to_owned());
+        self
+    }
+
+    /// Configures the stdin stream of the program
+    ///
+    /// Use [`StdioMode::Piped`] to write to the program through
+    /// [`Process::stdin`].
+    pub fn stdin<M: Into<StdioMode>>(&mut self, mode: M) -> &mut Self {
+        self.stdin = mode.into();
+        self.stdin_data = None;
+        self
+    }
+
+    /// Feeds `data` to the stdin stream of the program
+    ///
+    /// The data is written from a background thread once the program is
+    /// spawned, and stdin is closed afterwards, so the program can not
+    /// deadlock against output capture.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use xprocess::ProcessBuilder;
+    ///
+    /// let output = ProcessBuilder::new("sort")
+    ///     .stdin_bytes("b\na\n")
+    ///     .output()
+    ///     .expect("Failed to run sort");
+    /// assert_eq!(output.stdout, b"a\nb\n");
+    /// ```
+    pub fn stdin_bytes<D: Into<Vec<u8>>>(&mut self, data: D) -> &mut Self {
+        self.stdin = StdioMode::Piped;
+        self.stdin_data = Some(data.into());
+        self
+    }
+
+    /// Configures the stdout stream of the program
+    pub fn stdout<M: Into<StdioMode>>(&mut self, mode: M) -> &mut Self {
+        self.stdout = mode.into();
+        self
+    }
+
+    /// Configures the stderr stream of the program
+    pub fn stderr<M: Into<StdioMode>>(&mut self, mode: M) -> &mut Self {
+        self.stderr = mode.into();
+        self
+    }
+
+    /// Connects stdin, stdout and stderr of the program to a new
+    /// pseudo-terminal of `size`
+    ///
+    /// Programs checking `isatty(3)` then behave as in an interactive
+    /// session, e.g. line buffering their output or prompting for input.
+    /// The terminal is available through [`Process::pty`] and replaces any
+    /// other configuration of the standard streams, including
+    /// [`ProcessBuilder::stdin_bytes`].
+    ///
+    /// With [`Detach::Session`], the default, the terminal becomes the
+    /// controlling terminal of the new session so job control and
+    /// `/dev/tty` work as expected.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use xprocess::{ProcessBuilder, WindowSize};
+    ///
+    /// let output = ProcessBuilder::new("ls")
+    ///     .arg("--color=auto")
+    ///     .pty(WindowSize::new(50, 200))
+    ///     .output()
+    ///     .expect("Failed to run ls");
+    /// ```
+    pub fn pty(&mut self, size: WindowSize) -> &mut Self {
+        self.pty = Some(size);
+        self
+    }
+
+    /// Kills the process group of the program when the [`Process`] handle
+    /// is dropped
+    ///
+    /// The group receives [`Signal::Kill`] and the program is reaped before
+    /// the handle goes away. Disabled by default, in which case dropping the
+    /// handle leaves the program running. Use [`Process::detach`] to release
+    /// a program spawned with this option.
+    ///
+    /// [`Signal::Kill`]: crate::Signal::Kill
+    pub fn kill_on_drop(&mut self, kill_on_drop: bool) -> &mut Self {
+        self.kill_on_drop = kill_on_drop;
+        self
+    }
+
+    /// Configures how far the program is detached from the current process
+    ///
+    /// Defaults to [`Detach::Session`], which makes the program the leader of
+    /// a new session and process group.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use xprocess::{Detach, ProcessBuilder};
+    ///
+    /// // Ctrl-C in the terminal also interrupts `make`
+    /// let mut process = ProcessBuilder::new("make").detach(Detach::None).spawn().expect("Failed to spawn");
+    /// ```
+    pub fn detach(&mut self, detach: Detach) -> &mut Self {
+        self.pre_exec.detach = detach;
+        self
+    }
+
+    /// Limits the usage of `resource` by the program with `setrlimit(2)`
+    ///
+    /// Limits are applied in the child right before `exec`, after it was
+    /// detached, and are inherited by every process it spawns. Setting the
+    /// same resource twice keeps the last limit. Spawning fails if a limit
+    /// can not be applied, e.g. when raising a hard limit without privileges.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use xprocess::{ProcessBuilder, Resource, Rlimit};
+    ///
+    /// let output = ProcessBuilder::new("./untrusted.sh")
+    ///     .rlimit(Resource::CpuTime, Rlimit::fixed(10))
+    ///     .rlimit(Resource::AddressSpace, Rlimit::fixed(512 * 1024 * 1024))
+    ///     .rlimit(Resource::CoreSize, Rlimit::fixed(0))
+    ///     .output()
+    ///     .expect("Failed to run script");
+    /// ```
+    pub fn rlimit(&mut self, resource: Resource, limit: Rlimit) -> &mut Self {
+        let rlimits = &mut self.pre_exec.rlimits;
+        rlimits.retain(|(existing, _)| *existing != resource);
+        rlimits.push((resource, limit));
+        self
+    }
+
+    /// Sets the niceness of the program, from `-20` (highest priority) to
+    /// `19` (lowest priority)
+    ///
+    /// This is an absolute value, unlike the increment taken by `nice(1)`.
+    /// Lowering the niceness below the one of the caller requires
+    /// `CAP_SYS_NICE`, spawning fails otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use xprocess::ProcessBuilder;
+    ///
+    /// let process = ProcessBuilder::new("./batch-job").nice(19).spawn().expect("Failed to spawn");
+    /// ```
+    pub fn nice(&mut self, nice: i32) -> &mut Self {
+        self.pre_exec.nice = Some(nice);
+        self
+    }
+
+    /// Restricts the program to the CPUs at the given indices with
+    /// `sched_setaffinity(2)`
+    ///
+    /// Spawning fails if none of the CPUs is available.
+    #[cfg(target_os = "linux")]
+    pub fn cpu_affinity<I: IntoIterator<Item = usize>>(&mut self, cpus: I) -> &mut Self {
+        self.pre_exec.cpu_affinity = Some(cpus.into_iter().collect());
+        self
+    }
+
+    /// Sets the scheduling policy of the program
+    #[cfg(target_os = "linux")]
+    pub fn sched_policy(&mut self, policy: SchedPolicy) -> &mut Self {
+        self.pre_exec.sched_policy = Some(policy);
+        self
+    }
+
+    /// Sets the I/O scheduling class and priority of the program
+    #[cfg(target_os = "linux")]
+    pub fn io_priority(&mut self, priority: IoPriority) -> &mut Self {
+        self.pre_exec.io_priority = Some(priority);
+        self
+    }
+
+    /// Adjusts the likelihood of the program being picked by the OOM killer,
+    /// from `-1000` (never) to `1000` (first)
+    ///
+    /// Lowering the score below the one of the caller requires
+    /// `CAP_SYS_RESOURCE`, spawning fails otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use xprocess::{IoPriority, ProcessBuilder, SchedPolicy};
+    ///
+    /// // Keep batch jobs out of the way of interactive services
+    /// let process = ProcessBuilder::new("./batch-job")
+    ///     .nice(10)
+    ///     .sched_policy(SchedPolicy::Batch)
+    ///     .io_priority(IoPriority::Idle)
+    ///     .oom_score_adj(500)
+    ///     .spawn()
+    ///     .expect("Failed to spawn");
+    /// ```
+    #[cfg(target_os = "linux")]
+    pub fn oom_score_adj(&mut self, score: i32) -> &mut Self {
+        self.pre_exec.oom_score_adj = Some(score);
+        self
+    }
+
+    /// Runs the program as `user`, given by name or ID
+    ///
+    /// Unless set with [`ProcessBuilder::group`] and
+    /// [`ProcessBuilder::groups`], the primary and supplementary groups are
+    /// taken from the user database. A user ID without an entry there needs
+    /// an explicit [`ProcessBuilder::group`], so the program can not keep
+    /// the group of the caller.
+    ///
+    /// Names are resolved when spawning, which fails with
+    /// [`Error::UnknownUser`] if the user does not exist or a user ID
+    /// without entry was given no group. Switching users
+    /// requires `CAP_SETUID` and `CAP_SETGID`, usually meaning the caller
+    /// runs as root. Privileges are dropped after every other setting of
+    /// this builder was applied.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use xprocess::ProcessBuilder;
+    ///
+    /// let mut worker = ProcessBuilder::new("./worker")
+    ///     .user("nobody")
+    ///     .umask(0o077)
+    ///     .no_new_privs(true)
+    ///     .spawn()
+    ///     .expect("Failed to spawn worker");
+    /// ```
+    ///
+    /// [`Error::UnknownUser`]: crate::Error::UnknownUser
+    pub fn user<U: Into<User>>(&mut self, user: U) -> &mut Self {
+        self.user = Some(user.into());
+        self
+    }
+
+    /// Runs the program with `group`, given by name or ID, as its primary
+    /// group
+    ///
+    /// Names are resolved when spawning, which fails with
+    /// [`Error::UnknownGroup`] if the group does not exist.
+    ///
+    /// [`Error::UnknownGroup`]: crate::Error::UnknownGroup
+    pub fn group<G: Into<Group>>(&mut self, group: G) -> &mut Self {
+        self.group = Some(group.into());
+        self
+    }
+
+    /// Replaces the supplementary groups of the program
+    ///
+    /// An empty list removes every supplementary group.
+    pub fn groups<I, G>(&mut self, groups: I) -> &mut Self
+    where
+        I: IntoIterator<Item = G>,
+        G: Into<Group>,
+    {
+        self.groups = Some(groups.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets the file mode creation mask of the program
+    ///
+    /// Takes precedence over [`DaemonOptions::umask`] when daemonizing and
+    /// over the `0o022` default of [`Detach::Daemon`].
+    ///
+    /// [`DaemonOptions::umask`]: crate::DaemonOptions::umask
+    pub fn umask(&mut self, umask: u32) -> &mut Self {
+        self.pre_exec.umask = Some(umask as libc::mode_t);
+        self
+    }
+
+    /// Prevents the program and its descendants from gaining privileges
+    /// with `PR_SET_NO_NEW_PRIVS`
+    ///
+    /// Set-user-ID and set-group-ID bits and file capabilities are ignored
+    /// by `execve(2)` once enabled, and it can never be disabled again.
+    #[cfg(target_os = "linux")]
+    pub fn no_new_privs(&mut self, no_new_privs: bool) -> &mut Self {
+        self.pre_exec.no_new_privs = no_new_privs;
+        self
+    }
+
+    /// Builds a [`Command`] from the current configuration, detached as
+    /// described by `detach`
+    pub(crate) fn command(&self, detach: Detach) -> io::Result<Command> {
+        let mut command = Process::build_command(&self.program, &self.args);
+
+        if let Some(ref arg0) = self.arg0 {
+            command.arg0(arg0);
+        }
+
+        if self.env_clear {
+            command.env_clear();
+        }
+
+        for (key, val) in &self.envs {
+            match val {
+                Some(val) => command.env(key, val),
+                None => command.env_remove(key),
+            };
+        }
+
+        match self.current_dir {
+            Some(ref dir) => {
+                command.current_dir(dir);
+            }
+            None if detach == Detach::Daemon => {
+                command.current_dir("/");
+            }
+            None => {}
+        }
+
+        command
+            .stdin(Self::stdio(&self.stdin, detach)?)
+            .stdout(Self::stdio(&self.stdout, detach)?)
+            .stderr(Self::stdio(&self.stderr, detach)?);
+
+        Ok(command)
+    }
+
+    /// Builds the [`Stdio`] for `mode`, daemons only keep streams redirected
+    /// to files
+    fn stdio(mode: &StdioMode, detach: Detach) -> io::Result<Stdio> {
+        match mode {
+            StdioMode::Inherit | StdioMode::Piped if detach == Detach::Daemon => Ok(Stdio::null()),
+            mode => mode.to_stdio(),
+        }
+    }
+
+    /// Settings applied in the child before `exec`, with user and group
+    /// names resolved
+    pub(crate) fn pre_exec(&self) -> Result<PreExec> {
+        let mut pre_exec = self.pre_exec.clone();
+        pre_exec.credentials = Credentials::resolve(
+            self.user.as_ref(),
+            self.group.as_ref(),
+            self.groups.as_deref(),
+        )?;
+
+        Ok(pre_exec)
+    }
+
+    /// Spawns the program using the current configuration
+    pub fn spawn(&mut self) -> Result<Process> {
+        self.spawn_with(self.pre_exec()?, None, None)
+    }
+
+    /// Spawns the program with `pre_exec`, connecting stdin and stdout to
+    /// the provided streams instead of the configured ones
+    pub(crate) fn spawn_with(
+        &self,
+        mut pre_exec: PreExec,
+        stdin: Option<Stdio>,
+        stdout: Option<Stdio>,
+    ) -> Result<Process> {
+        let mut command = self.command(pre_exec.detach)?;
+
+        if pre_exec.detach == Detach::Daemon {
+            pre_exec.umask.get_or_insert(DEFAULT_UMASK as libc::mode_t);
+        }
+
+        let pty = match self.pty {
+            Some(size) => {
+                let (pty, terminal) = Pty::open(size)?;
+                command
+                    .stdin(terminal.try_clone()?)
+                    .stdout(terminal.try_clone()?)
+                    .stderr(terminal);
+                pre_exec.controlling_terminal = pre_exec.detach == Detach::Session;
+                Some(pty)
+            }
+            None => None,
+        };
+
+        if let Some(stdin) = stdin {
+            command.stdin(stdin);
+        }
+
+        if let Some(stdout) = stdout {
+            command.stdout(stdout);
+        }
+
+        let mut process = Process::spawn_child_process(&mut command, pre_exec)?;
+        process.set_kill_on_drop(self.kill_on_drop);
+
+        match pty {
+            Some(pty) => process.set_pty(pty),
+            None => {
+                if let Some(ref data) = self.stdin_data {
+                    process.feed_stdin(data.clone());
+                }
+            }
+        }
+
+        Ok(process)
+    }
+
+    /// Spawns the program, waits for it to exit and collects its output
+    ///
+    /// This is the recommended way to run a program to completion, stdout
+    /// and stderr are drained concurrently as described in
+    /// [`Process::wait_with_output`].
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use xprocess::ProcessBuilder;
+    ///
+    /// let output = ProcessBuilder::new("uname").arg("-s").output().expect("Failed to run uname");
+    /// println!("{}", String::from_utf8_lossy(&output.stdout));
+    /// ```
+    pub fn output(&mut self) -> Result<Output> {
+        self.spawn()?.wait_with_output()
+    }
+
+    /// Spawns the program as an [`AsyncProcess`] whose pipes are driven by
+    /// the tokio runtime
+    ///
+    /// The process is set up exactly like with [`ProcessBuilder::spawn`].
+    ///
+    /// **Important:** Must be called from within a tokio runtime.
+    #[cfg(feature = "tokio")]
+    pub fn spawn_async(&mut self) -> Result<AsyncProcess> {
+        AsyncProcess::new(self.spawn()?)
+    }
+
+    /// Spawns the program, waits for it to exit and collects its output
+    /// without blocking the tokio runtime
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use xprocess::ProcessBuilder;
+    ///
+    /// let output = ProcessBuilder::new("uname").arg("-s").output_async().await.expect("Failed to run uname");
+    /// println!("{}", String::from_utf8_lossy(&output.stdout));
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub async fn output_async(&mut self) -> Result<Output> {
+        self.spawn_async()?.wait_with_output().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Seek, Write};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::Error;
+
+    #[test]
+    fn sets_env_vars() {
+        let mut process = ProcessBuilder::new("sh")
+            .args(["-c", "echo $FOO-$BAR"])
+            .env("FOO", "foo")
+            .envs([("BAR", "bar")])
+            .spawn()
+            .expect("Failed to spawn process");
+        let stdout = process.stdout().expect("Failed to read stdout");
+        assert_eq!(stdout.trim(), "foo-bar");
+    }
+
+    #[test]
+    fn removes_and_clears_env_vars() {
+        let mut process = ProcessBuilder::new("/bin/sh")
+            .args(["-c", "echo \"[$FOO][$HOME]\""])
+            .env("FOO", "foo")
+            .env_clear()
+            .spawn()
+            .expect("Failed to spawn process");
+        let stdout = process.stdout().expect("Failed to read stdout");
+        assert_eq!(stdout.trim(), "[][]");
+
+        let mut process = ProcessBuilder::new("sh")
+            .args(["-c", "echo \"[$FOO]\""])
+            .env("FOO", "foo")
+            .env_remove("FOO")
+            .spawn()
+            .expect("Failed to spawn process");
+        let stdout = process.stdout().expect("Failed to read stdout");
+        assert_eq!(stdout.trim(), "[]");
+    }
+
+    #[test]
+    fn sets_current_dir() {
+        let dir = std::env::temp_dir().canonicalize().unwrap();
+        let mut process = ProcessBuilder::new("pwd")
+            .current_dir(&dir)
+            .spawn()
+            .expect("Failed to spawn process");
+        let stdout = process.stdout().expect("Failed to read stdout");
+        assert_eq!(Path::new(stdout.trim()), dir);
+
+        let err = ProcessBuilder::new("pwd")
+            .current_dir("/xprocess-missing-dir")
+            .spawn()
+            .expect_err("Directory should not exist");
+        assert!(matches!(err, Error::Spawn { .. }), "{err}");
+        assert_eq!(err.errno(), Some(libc::ENOENT));
+    }
+
+    #[test]
+    fn redirects_stdout_to_file() {
+        let path = std::env::temp_dir().join(format!("xprocess-builder-{}", std::process::id()));
+        let file = File::options()
+            .create(true)
+            .truncate(true)
+            .read(true)
+            .write(true)
+            .open(&path)
+            .unwrap();
+        let mut process = ProcessBuilder::new("echo")
+            .arg("to file")
+            .stdout(file.try_clone().unwrap())
+            .spawn()
+            .expect("Failed to spawn process");
+
+        // Stdout is not piped, so there is nothing to read from the process
+        assert_eq!(process.stdout().expect("Failed to read stdout"), "");
+        process.stderr().expect("Failed to read stderr");
+
+        let mut file = file;
+        let mut contents = String::new();
+        file.rewind().unwrap();
+        file.read_to_string(&mut contents).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(contents.trim(), "to file");
+    }
+
+    #[test]
+    fn null_stdout() {
+        let mut process = ProcessBuilder::new("echo")
+            .arg("discarded")
+            .stdout(StdioMode::Null)
+            .spawn()
+            .expect("Failed to spawn process");
+        assert_eq!(process.stdout().expect("Failed to read stdout"), "");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn overrides_arg0() {
+        let mut process = ProcessBuilder::new("cat")
+            .arg("/proc/self/cmdline")
+            .arg0("custom-name")
+            .spawn()
+            .expect("Failed to spawn process");
+        let stdout = process.stdout().expect("Failed to read stdout");
+        assert!(stdout.starts_with("custom-name\0"));
+    }
+
+    #[test]
+    fn runs_to_completion() {
+        let output = ProcessBuilder::new("sh")
+            .args(["-c", "printf \"$FOO\"; exit 1"])
+            .env("FOO", "foo")
+            .output()
+            .expect("Failed to run process");
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(output.stdout, b"foo");
+        assert!(output.stderr.is_empty());
+    }
+
+    #[test]
+    fn feeds_stdin_bytes() {
+        let output = ProcessBuilder::new("sort")
+            .stdin_bytes("b\nc\na\n")
+            .output()
+            .expect("Failed to run process");
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"a\nb\nc\n");
+    }
+
+    #[test]
+    fn feeds_large_stdin_while_capturing_output() {
+        let input = vec![b'x'; 1_000_000];
+        let output = ProcessBuilder::new("cat")
+            .stdin_bytes(input.clone())
+            .output()
+            .expect("Failed to run process");
+        assert_eq!(output.stdout, input);
+    }
+
+    #[test]
+    fn ignores_unread_stdin() {
+        let output = ProcessBuilder::new("true")
+            .stdin_bytes(vec![0; 1_000_000])
+            .output()
+            .expect("Failed to run process");
+        assert!(output.status.success());
+    }
+
+    #[test]
+    fn wait_does_not_block_on_stdin_held_by_descendant() {
+        let mut process = ProcessBuilder::new("sh")
+            .args(["-c", "exec 3<&0; sleep 5 0<&3 & exit 0"])
+            .stdin_bytes(vec![b'x'; 1_000_000])
+            .stdout(StdioMode::Null)
+            .stderr(StdioMode::Null)
+            .spawn()
+            .expect("Failed to spawn process");
+
+        let started = Instant::now();
+        let status = loop {
+            if let Some(status) = process.try_wait().expect("Failed to check process") {
+                break status;
+            }
+
+            assert!(
+                started.elapsed() < Duration::from_secs(2),
+                "Process did not exit"
+            );
+            thread::sleep(Duration::from_millis(10));
+        };
+        assert!(status.success());
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert!(process.is_alive().is_ok_and(|alive| !alive));
+        assert_eq!(process.wait().expect("Failed to wait for process"), status);
+        process.kill_group().expect("Failed to kill descendant");
+
+        let mut process = ProcessBuilder::new("sh")
+            .args(["-c", "exec 3<&0; sleep 5 0<&3 & exit 0"])
+            .stdin_bytes(vec![b'x'; 1_000_000])
+            .stdout(StdioMode::Null)
+            .stderr(StdioMode::Null)
+            .spawn()
+            .expect("Failed to spawn process");
+
+        let started = Instant::now();
+        assert!(
+            process
+                .wait()
+                .expect("Failed to wait for process")
+                .success()
+        );
+        assert!(started.elapsed() < Duration::from_secs(2));
+        process.kill_group().expect("Failed to kill descendant");
+    }
+
+    #[test]
+    fn writes_to_piped_stdin() {
+        let mut process = ProcessBuilder::new("cat")
+            .stdin(StdioMode::Piped)
+            .spawn()
+            .expect("Failed to spawn process");
+        let stdin = process.stdin().expect("Stdin is not piped");
+        stdin.write_all(b"hello ").unwrap();
+        stdin.write_all(b"stdin").unwrap();
+        process.close_stdin();
+        assert!(process.stdin().is_none());
+        assert_eq!(
+            process.stdout().expect("Failed to read stdout"),
+            "hello stdin"
+        );
+    }
+
+    #[test]
+    fn reads_stdin_from_file() {
+        let path = std::env::temp_dir().join(format!("xprocess-stdin-{}", std::process::id()));
+        std::fs::write(&path, "from file").unwrap();
+        let output = ProcessBuilder::new("cat")
+            .stdin(File::open(&path).unwrap())
+            .output()
+            .expect("Failed to run process");
+        std::fs::remove_file(&path).ok();
+        assert_eq!(output.stdout, b"from file");
+    }
+
+    #[test]
+    fn spawns_more_than_once() {
+        let mut builder = ProcessBuilder::new("echo");
+        builder.arg("again");
+
+        for _ in 0..2 {
+            let output = builder.output().expect("Failed to run process");
+            assert_eq!(output.stdout, b"again\n");
+        }
+    }
+
+    #[test]
+    fn stays_in_parent_group() {
+        let mut process = ProcessBuilder::new("sleep")
+            .arg("10")
+            .detach(Detach::None)
+            .kill_on_drop(true)
+            .spawn()
+            .expect("Failed to spawn process");
+        let pgid = unsafe { libc::getpgrp() } as u32;
+        assert_eq!(process.pgid().expect("Failed to get pgid"), pgid);
+        assert!(matches!(
+            process.kill_group(),
+            Err(Error::NoProcessGroup { .. })
+        ));
+        process.force_kill().expect("Failed to kill process");
+        process.wait().expect("Failed to wait for process");
+    }
+
+    #[test]
+    fn creates_process_group() {
+        let mut process = ProcessBuilder::new("sleep")
+            .arg("10")
+            .detach(Detach::ProcessGroup)
+            .spawn()
+            .expect("Failed to spawn process");
+        let sid = unsafe { libc::getsid(0) } as u32;
+        assert_eq!(process.pgid().expect("Failed to get pgid"), process.pid());
+        assert_eq!(process.sid().expect("Failed to get sid"), sid);
+        process.kill_group().expect("Failed to kill process group");
+        process.wait().expect("Failed to wait for process");
+    }
+
+    #[test]
+    fn daemon_defaults_to_root_dir() {
+        let path = std::env::temp_dir().join(format!("xprocess-detach-{}", std::process::id()));
+        let file = File::create(&path).unwrap();
+        let output = ProcessBuilder::new("sh")
+            .args(["-c", "pwd; umask; echo hidden >&2"])
+            .detach(Detach::Daemon)
+            .stdout(file)
+            .output()
+            .expect("Failed to run process");
+        assert!(output.status.success());
+        // Piped streams are replaced with `/dev/null`
+        assert!(output.stderr.is_empty());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "/\n0022\n");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn applies_rlimits() {
+        let output = ProcessBuilder::new("sh")
+            .args(["-c", "ulimit -Sn; ulimit -Hn; ulimit -c"])
+            .rlimit(Resource::OpenFiles, Rlimit::fixed(128))
+            .rlimit(Resource::OpenFiles, Rlimit::new(64, 128))
+            .rlimit(Resource::CoreSize, Rlimit::fixed(0))
+            .output()
+            .expect("Failed to run process");
+        assert_eq!(output.stdout, b"64\n128\n0\n");
+    }
+
+    #[test]
+    fn rejects_invalid_rlimit() {
+        // The soft limit can not exceed the hard limit
+        let err = ProcessBuilder::new("true")
+            .rlimit(Resource::OpenFiles, Rlimit::new(128, 64))
+            .spawn()
+            .expect_err("Limit should be rejected");
+        assert_eq!(err.errno(), Some(libc::EINVAL));
+
+        // Raising the limit above `fs.nr_open` fails even for root, which is
+        // not mistaken for a permission problem of the program
+        #[cfg(target_os = "linux")]
+        {
+            let err = ProcessBuilder::new("true")
+                .rlimit(Resource::OpenFiles, Rlimit::fixed(u64::MAX - 1))
+                .spawn()
+                .expect_err("Limit should be rejected");
+            assert!(matches!(err, Error::Spawn { .. }), "{err}");
+            assert_eq!(err.errno(), Some(libc::EPERM));
+        }
+    }
+
+    #[test]
+    fn sets_niceness() {
+        let output = ProcessBuilder::new("nice")
+            .nice(7)
+            .output()
+            .expect("Failed to run process");
+        assert_eq!(output.stdout, b"7\n");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn applies_scheduling() {
+        let mut process = ProcessBuilder::new("sleep")
+            .arg("10")
+            .cpu_affinity([0])
+            .sched_policy(SchedPolicy::Batch)
+            .io_priority(IoPriority::BestEffort(6))
+            .oom_score_adj(300)
+            .kill_on_drop(true)
+            .spawn()
+            .expect("Failed to spawn process");
+        let pid = process.pid() as libc::pid_t;
+
+        let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+        assert_eq!(
+            unsafe { libc::sched_getaffinity(pid, size_of::<libc::cpu_set_t>(), &mut set) },
+            0
+        );
+        assert_eq!(unsafe { libc::CPU_COUNT(&set) }, 1);
+        assert!(unsafe { libc::CPU_ISSET(0, &set) });
+        assert_eq!(unsafe { libc::sched_getscheduler(pid) }, libc::SCHED_BATCH);
+        assert_eq!(
+            unsafe { libc::syscall(libc::SYS_ioprio_get, 1, pid) },
+            (2 << 13) | 6
+        );
+        let score = std::fs::read_to_string(format!("/proc/{pid}/oom_score_adj"))
+            .expect("Failed to read oom_score_adj");
+        assert_eq!(score.trim(), "300");
+
+        process.force_kill().expect("Failed to kill process");
+        process.wait().expect("Failed to wait for process");
+    }
+
+    #[test]
+    fn drops_privileges() {
+        if unsafe { libc::geteuid() } != 0 {
+            return;
+        }
+
+        let nobody = unsafe { libc::getpwnam(c"nobody".as_ptr()) };
+
+        if nobody.is_null() {
+            return;
+        }
+
+        let (uid, gid) = unsafe { ((*nobody).pw_uid, (*nobody).pw_gid) };
+        let output = ProcessBuilder::new("id")
+            .user("nobody")
+            .output()
+            .expect("Failed to run process");
+        let expected = format!("uid={uid}(nobody) gid={gid}(");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.starts_with(&expected), "{stdout}");
+        assert!(!stdout.contains("(root)"), "{stdout}");
+
+        let output = ProcessBuilder::new("sh")
+            .args(["-c", "id -u; id -g; id -G"])
+            .user(uid)
+            .group(0)
+            .groups([gid])
+            .output()
+            .expect("Failed to run process");
+        assert_eq!(output.stdout, format!("{uid}\n0\n0 {gid}\n").as_bytes());
+    }
+
+    #[test]
+    fn rejects_unknown_user() {
+        let err = ProcessBuilder::new("true")
+            .user("xprocess-missing-user")
+            .spawn()
+            .expect_err("User should not exist");
+        assert!(matches!(err, Error::UnknownUser { .. }));
+
+        let err = ProcessBuilder::new("true")
+            .user(54321)
+            .spawn()
+            .expect_err("User ID without group should be rejected");
+        assert!(matches!(err, Error::UnknownUser { .. }));
+    }
+
+    #[test]
+    fn sets_umask() {
+        let output = ProcessBuilder::new("sh")
+            .args(["-c", "umask"])
+            .umask(0o027)
+            .output()
+            .expect("Failed to run process");
+        assert_eq!(output.stdout, b"0027\n");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn sets_no_new_privs() {
+        let output = ProcessBuilder::new("grep")
+            .args(["NoNewPrivs", "/proc/self/status"])
+            .no_new_privs(true)
+            .output()
+            .expect("Failed to run process");
+        assert_eq!(output.stdout, b"NoNewPrivs:\t1\n");
+    }
+
+    #[test]
+    fn spawns_in_pty() {
+        let output = ProcessBuilder::new("sh")
+            .args([
+                "-c",
+                "test -t 0 && test -t 1 && test -t 2 && stty size && echo ok > /dev/tty",
+            ])
+            .pty(WindowSize::new(30, 100))
+            .output()
+            .expect("Failed to run process");
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"30 100\r\nok\r\n");
+        assert!(output.stderr.is_empty());
+    }
+}
@@ -0,0 +1,251 @@
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+
+use crate::{Detach, Error, ProcessBuilder, Result};
+
+/// File mode creation mask used by [`DaemonOptions::default`] and
+/// [`Detach::Daemon`]
+pub(crate) const DEFAULT_UMASK: u32 = 0o022;
+
+/// Options used by [`Process::daemonize`]
+///
+/// [`Process::daemonize`]: crate::Process::daemonize
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DaemonOptions {
+    pidfile: Option<PathBuf>,
+    umask: u32,
+}
+
+impl DaemonOptions {
+    /// Creates options without a pidfile and with a `0o022` umask
+    pub fn new() -> Self {
+        Self {
+            pidfile: None,
+            umask: DEFAULT_UMASK,
+        }
+    }
+
+    /// Writes the PID of the daemon to `path`
+    ///
+    /// The file is protected with an exclusive `flock(2)` held by the daemon
+    /// for as long as it keeps the file descriptor open, which prevents a
+    /// second instance from being started with the same pidfile.
+    pub fn pidfile<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.pidfile = Some(path.as_ref().to_owned());
+        self
+    }
+
+    /// Sets the file mode creation mask of the daemon
+    pub fn umask(mut self, umask: u32) -> Self {
+        self.umask = umask;
+        self
+    }
+}
+
+impl Default for DaemonOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Spawns the program configured by `builder` as a daemon and returns the
+/// PID of the daemon
+///
+/// The child becomes a session leader with `setsid(2)` and forks again, the
+/// intermediate process exits right away so the daemon is reparented to
+/// `init` and can never reacquire a controlling terminal.
+pub(crate) fn daemonize(builder: &ProcessBuilder, options: &DaemonOptions) -> Result<u32> {
+    let mut command = builder.command(Detach::Daemon)?;
+    let pre_exec = builder.pre_exec()?;
+    let program = command.get_program().to_string_lossy().into_owned();
+    let mut pidfile = options.pidfile.as_deref().map(Pidfile::lock).transpose()?;
+    let (mut reader, writer) = io::pipe()?;
+    let writer_fd = writer.as_raw_fd();
+    let pidfile_fd = pidfile.as_ref().map(|pidfile| pidfile.file.as_raw_fd());
+    let umask = options.umask as libc::mode_t;
+
+    unsafe {
+        command.pre_exec(move || {
+            if libc::setsid() < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            match libc::fork() {
+                -1 => Err(io::Error::last_os_error()),
+                0 => {
+                    libc::umask(umask);
+                    pre_exec.configure()?;
+
+                    // Keep the pidfile, and therefore its lock, open in the daemon
+                    if let Some(fd) = pidfile_fd
+                        && libc::fcntl(fd, libc::F_SETFD, 0) < 0
+                    {
+                        return Err(io::Error::last_os_error());
+                    }
+
+                    Ok(())
+                }
+                pid => {
+                    // Report the PID of the daemon and let it be reparented
+                    let bytes = pid.to_ne_bytes();
+                    libc::write(writer_fd, bytes.as_ptr().cast(), bytes.len());
+                    libc::_exit(0)
+                }
+            }
+        });
+    }
+
+    let mut child = command.spawn().map_err(|err| Error::spawn(&command, err))?;
+
+    // Only the forked processes should hold the write end of the pipe now
+    drop(writer);
+    child.wait()?;
+
+    let mut bytes = [0; size_of::<libc::pid_t>()];
+    reader
+        .read_exact(&mut bytes)
+        .map_err(|source| Error::Spawn {
+            command: program,
+            source,
+        })?;
+    let pid = libc::pid_t::from_ne_bytes(bytes) as u32;
+
+    if let Some(ref mut pidfile) = pidfile {
+        pidfile.write_pid(pid)?;
+    }
+
+    Ok(pid)
+}
+
+/// Pidfile locked for a daemon being spawned
+///
+/// Existing content is only replaced once the PID of the daemon is known,
+/// and a pidfile created for a daemon which failed to spawn is removed
+/// again, so tooling never reads an empty pidfile.
+struct Pidfile {
+    file: File,
+    path: PathBuf,
+    /// Whether the file was created by [`Pidfile::lock`]
+    created: bool,
+    written: bool,
+}
+
+impl Pidfile {
+    /// Opens and locks the pidfile at `path`
+    fn lock(path: &Path) -> Result<Self> {
+        let mut options = File::options();
+        options.read(true).write(true);
+
+        let (file, created) = match options.clone().create_new(true).open(path) {
+            Ok(file) => (file, true),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => (options.open(path)?, false),
+            Err(err) => return Err(err.into()),
+        };
+        let pidfile = Self {
+            file,
+            path: path.to_owned(),
+            created,
+            written: false,
+        };
+
+        if unsafe { libc::flock(pidfile.file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } < 0 {
+            let err = io::Error::last_os_error();
+
+            if err.raw_os_error() == Some(libc::EWOULDBLOCK) {
+                return Err(Error::PidfileLocked {
+                    path: path.to_owned(),
+                });
+            }
+
+            return Err(err.into());
+        }
+
+        Ok(pidfile)
+    }
+
+    /// Replaces any stale content with `pid`
+    fn write_pid(&mut self, pid: u32) -> io::Result<()> {
+        self.file.set_len(0)?;
+        writeln!(self.file, "{pid}")?;
+        self.file.sync_all()?;
+        self.written = true;
+        Ok(())
+    }
+}
+
+impl Drop for Pidfile {
+    fn drop(&mut self) {
+        if self.created && !self.written {
+            std::fs::remove_file(&self.path).ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::Process;
+
+    #[test]
+    fn daemonizes_with_pidfile() {
+        let pidfile =
+            std::env::temp_dir().join(format!("xprocess-daemon-{}.pid", std::process::id()));
+        let options = DaemonOptions::new().pidfile(&pidfile);
+        let pid = Process::daemonize(ProcessBuilder::new("sleep").arg("5"), &options)
+            .expect("Failed to daemonize process");
+
+        let contents = std::fs::read_to_string(&pidfile).expect("Failed to read pidfile");
+        assert_eq!(contents.trim(), pid.to_string());
+
+        // The daemon is neither our child nor a session leader
+        let mut status = 0;
+        let result = unsafe { libc::waitpid(pid as libc::pid_t, &mut status, libc::WNOHANG) };
+        assert_eq!(result, -1);
+        let sid = unsafe { libc::getsid(pid as libc::pid_t) };
+        assert!(sid > 0 && sid as u32 != pid);
+        assert_ne!(sid, unsafe { libc::getsid(0) });
+
+        // The lock held by the running daemon rejects a second instance
+        let err = Process::daemonize(ProcessBuilder::new("sleep").arg("5"), &options)
+            .expect_err("Pidfile should be locked");
+        assert!(matches!(err, Error::PidfileLocked { .. }));
+
+        unsafe { libc::kill(pid as libc::pid_t, libc::SIGKILL) };
+        thread::sleep(Duration::from_millis(100));
+        std::fs::remove_file(&pidfile).ok();
+    }
+
+    #[test]
+    fn reports_missing_program() {
+        let err = Process::daemonize(
+            &ProcessBuilder::new("xprocess-missing-command"),
+            &DaemonOptions::default(),
+        )
+        .expect_err("Command should not exist");
+        assert!(matches!(err, Error::NotFound { .. }));
+    }
+
+    #[test]
+    fn cleans_up_pidfile_of_failed_daemon() {
+        let pidfile =
+            std::env::temp_dir().join(format!("xprocess-failed-{}.pid", std::process::id()));
+        let options = DaemonOptions::new().pidfile(&pidfile);
+        let builder = ProcessBuilder::new("xprocess-missing-command");
+
+        let err = Process::daemonize(&builder, &options).expect_err("Command should not exist");
+        assert!(matches!(err, Error::NotFound { .. }));
+        assert!(!pidfile.exists(), "Pidfile should be removed");
+
+        std::fs::write(&pidfile, "123\n").unwrap();
+        let err = Process::daemonize(&builder, &options).expect_err("Command should not exist");
+        assert!(matches!(err, Error::NotFound { .. }));
+        assert_eq!(std::fs::read_to_string(&pidfile).unwrap(), "123\n");
+        std::fs::remove_file(&pidfile).unwrap();
+    }
+}
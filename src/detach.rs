@@ -0,0 +1,52 @@
+use std::io;
+
+/// Controls how far a spawned [`Process`] is detached from its parent
+///
+/// [`Process`]: crate::Process
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Detach {
+    /// Stays in the process group and session of the parent, so signals
+    /// generated by the terminal, such as Ctrl-C, also reach the process
+    None,
+    /// Moves the process to a new process group within the session of the
+    /// parent using `setpgid(2)`
+    ProcessGroup,
+    /// Moves the process to a new session using `setsid(2)`, which also
+    /// creates a new process group and drops the controlling terminal
+    #[default]
+    Session,
+    /// Behaves like [`Detach::Session`] and additionally follows the
+    /// conventions of `daemon(3)`: the working directory defaults to `/`,
+    /// the umask to `0o022`, and standard streams which are inherited or
+    /// piped are replaced with `/dev/null`
+    ///
+    /// The process stays a child of the caller. Use [`Process::daemonize`]
+    /// for a daemon reparented to `init` which outlives the caller.
+    ///
+    /// [`Process::daemonize`]: crate::Process::daemonize
+    Daemon,
+}
+
+impl Detach {
+    /// Returns `true` if the process leads a process group of its own
+    pub fn new_process_group(self) -> bool {
+        !matches!(self, Detach::None)
+    }
+
+    /// Applies the detachment in the child process, right before `exec`
+    ///
+    /// Only async-signal-safe functions may be called here.
+    pub(crate) fn apply(self) -> io::Result<()> {
+        let result = match self {
+            Detach::None => 0,
+            Detach::ProcessGroup => unsafe { libc::setpgid(0, 0) },
+            Detach::Session | Detach::Daemon => unsafe { libc::setsid() },
+        };
+
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,255 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::process::Command;
+use std::string::FromUtf8Error;
+
+/// Result type returned by every fallible operation of this crate
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Errors produced while spawning and managing a [`Process`]
+///
+/// [`Process`]: crate::Process
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The program to spawn could not be found (`ENOENT`)
+    NotFound { command: String, source: io::Error },
+    /// The caller is not allowed to execute the program (`EACCES`)
+    PermissionDenied { command: String, source: io::Error },
+    /// The program could not be spawned for any other reason, including
+    /// failures to apply the settings of [`ProcessBuilder`] in the child
+    ///
+    /// [`ProcessBuilder`]: crate::ProcessBuilder
+    Spawn { command: String, source: io::Error },
+    /// The process does not exist, usually because it already exited
+    /// (`ESRCH`)
+    NoSuchProcess { pid: u32 },
+    /// The caller is not allowed to act on the process (`EPERM`)
+    NotPermitted { pid: u32 },
+    /// The output of the process is not valid UTF-8
+    NotUtf8 {
+        pid: u32,
+        stream: &'static str,
+        source: FromUtf8Error,
+    },
+    /// The requested standard stream is not piped or was already taken
+    StreamUnavailable { pid: u32, stream: &'static str },
+    /// The operation is only supported on children of the current process
+    NotAChild { pid: u32 },
+    /// The process does not lead a process group of its own
+    NoProcessGroup { pid: u32 },
+    /// The pidfile of a daemon is locked by a running instance
+    PidfileLocked { path: PathBuf },
+    /// No user with this name exists in the user database
+    UnknownUser { name: String },
+    /// No group with this name exists in the group database
+    UnknownGroup { name: String },
+    /// The syntax of a [`Pattern`] is not supported
+    ///
+    /// [`Pattern`]: crate::Pattern
+    InvalidPattern { pattern: String, reason: String },
+    /// The process did not produce the expected output in time
+    Timeout { pid: u32, expected: String },
+    /// The process closed its output before producing the expected output
+    UnexpectedEof { pid: u32, expected: String },
+    /// A [`Pipeline`] was spawned without any stage
+    ///
+    /// [`Pipeline`]: crate::Pipeline
+    EmptyPipeline,
+    /// A system call on the process failed
+    Os {
+        pid: u32,
+        operation: &'static str,
+        errno: i32,
+    },
+    /// An I/O operation on the pipes of the process failed
+    Io(io::Error),
+}
+
+impl Error {
+    /// Builds an error for a failed `spawn` of `command`
+    ///
+    /// Failures of `exec` and of the settings applied before it are both
+    /// reported through a bare errno. `ENOENT` and `EACCES` are attributed
+    /// to the program unless its working directory is missing, any other
+    /// errno comes from a setting such as a resource limit.
+    pub(crate) fn spawn(command: &Command, source: io::Error) -> Self {
+        let program = command.get_program().to_string_lossy().into_owned();
+        let missing_dir = command.get_current_dir().is_some_and(|dir| !dir.is_dir());
+
+        match source.raw_os_error() {
+            Some(libc::ENOENT) if !missing_dir => Error::NotFound {
+                command: program,
+                source,
+            },
+            Some(libc::EACCES) if !missing_dir => Error::PermissionDenied {
+                command: program,
+                source,
+            },
+            _ => Error::Spawn {
+                command: program,
+                source,
+            },
+        }
+    }
+
+    /// Builds an error for `operation` on `pid` from the current value of
+    /// `errno`
+    pub(crate) fn last_os_error(pid: u32, operation: &'static str) -> Self {
+        Self::from_io(pid, operation, io::Error::last_os_error())
+    }
+
+    /// Builds an error for `operation` on `pid` from an [`io::Error`]
+    /// reported by the operating system
+    pub(crate) fn from_io(pid: u32, operation: &'static str, err: io::Error) -> Self {
+        let Some(errno) = err.raw_os_error() else {
+            return Error::Io(err);
+        };
+
+        match errno {
+            libc::ESRCH => Error::NoSuchProcess { pid },
+            libc::EPERM => Error::NotPermitted { pid },
+            _ => Error::Os {
+                pid,
+                operation,
+                errno,
+            },
+        }
+    }
+
+    /// Retrieves the OS error number behind this error, if any
+    pub fn errno(&self) -> Option<i32> {
+        match self {
+            Error::NoSuchProcess { .. } => Some(libc::ESRCH),
+            Error::NotPermitted { .. } => Some(libc::EPERM),
+            Error::PidfileLocked { .. } => Some(libc::EWOULDBLOCK),
+            Error::Os { errno, .. } => Some(*errno),
+            Error::NotFound { source, .. }
+            | Error::PermissionDenied { source, .. }
+            | Error::Spawn { source, .. }
+            | Error::Io(source) => source.raw_os_error(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NotFound { command, .. } => write!(f, "Command not found: {command}"),
+            Error::PermissionDenied { command, .. } => {
+                write!(f, "Permission denied executing command: {command}")
+            }
+            Error::Spawn { command, source } => {
+                write!(f, "Failed to spawn command {command}: {source}")
+            }
+            Error::NoSuchProcess { pid } => write!(f, "No process with PID: {pid}"),
+            Error::NotPermitted { pid } => {
+                write!(f, "Not allowed to act on process with PID: {pid}")
+            }
+            Error::NotUtf8 { pid, stream, .. } => {
+                write!(
+                    f,
+                    "The {stream} of process with PID {pid} is not valid UTF-8"
+                )
+            }
+            Error::StreamUnavailable { pid, stream } => write!(
+                f,
+                "The {stream} of process with PID {pid} is not piped or was already taken"
+            ),
+            Error::NotAChild { pid } => {
+                write!(f, "Process with PID {pid} is not a child process")
+            }
+            Error::NoProcessGroup { pid } => {
+                write!(f, "Process with PID {pid} does not lead a process group")
+            }
+            Error::PidfileLocked { path } => {
+                write!(f, "Pidfile {} is locked by another process", path.display())
+            }
+            Error::UnknownUser { name } => write!(f, "Unknown user: {name}"),
+            Error::UnknownGroup { name } => write!(f, "Unknown group: {name}"),
+            Error::InvalidPattern { pattern, reason } => {
+                write!(f, "Invalid pattern {pattern}: {reason}")
+            }
+            Error::Timeout { pid, expected } => write!(
+                f,
+                "Timed out waiting for {expected} from process with PID {pid}"
+            ),
+            Error::UnexpectedEof { pid, expected } => write!(
+                f,
+                "Process with PID {pid} closed its output while waiting for {expected}"
+            ),
+            Error::EmptyPipeline => write!(f, "Pipeline has no stages"),
+            Error::Os {
+                pid,
+                operation,
+                errno,
+            } => write!(
+                f,
+                "Failed to {operation} process with PID {pid}: {}",
+                io::Error::from_raw_os_error(*errno)
+            ),
+            Error::Io(err) => write!(f, "I/O error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::NotFound { source, .. }
+            | Error::PermissionDenied { source, .. }
+            | Error::Spawn { source, .. }
+            | Error::Io(source) => Some(source),
+            Error::NotUtf8 { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_spawn_errors() {
+        let command = Command::new("foo");
+        let err = Error::spawn(&command, io::Error::from_raw_os_error(libc::ENOENT));
+        assert!(matches!(err, Error::NotFound { ref command, .. } if command == "foo"));
+        assert_eq!(err.errno(), Some(libc::ENOENT));
+
+        let err = Error::spawn(&command, io::Error::from_raw_os_error(libc::EACCES));
+        assert!(matches!(err, Error::PermissionDenied { .. }));
+
+        // `EPERM` comes from a setting applied before `exec`
+        let err = Error::spawn(&command, io::Error::from_raw_os_error(libc::EPERM));
+        assert!(matches!(err, Error::Spawn { .. }));
+        assert_eq!(err.errno(), Some(libc::EPERM));
+
+        let mut command = Command::new("foo");
+        command.current_dir("/xprocess-missing-dir");
+        let err = Error::spawn(&command, io::Error::from_raw_os_error(libc::ENOENT));
+        assert!(matches!(err, Error::Spawn { .. }));
+        assert_eq!(err.errno(), Some(libc::ENOENT));
+    }
+
+    #[test]
+    fn displays_os_error() {
+        let err = Error::Os {
+            pid: 1,
+            operation: "signal",
+            errno: libc::EINVAL,
+        };
+        assert!(
+            err.to_string()
+                .starts_with("Failed to signal process with PID 1: ")
+        );
+    }
+}
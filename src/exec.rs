@@ -0,0 +1,108 @@
+use std::io;
+
+use crate::user::Credentials;
+use crate::{Detach, Resource, Rlimit, pty, sched};
+#[cfg(target_os = "linux")]
+use crate::{IoPriority, SchedPolicy};
+
+/// Settings applied in the child process between `fork` and `exec`
+///
+/// Everything here runs in the forked child of a possibly multi-threaded
+/// parent, so only async-signal-safe functions may be called: values are
+/// prepared by [`ProcessBuilder`] before spawning and nothing is allocated.
+///
+/// [`ProcessBuilder`]: crate::ProcessBuilder
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PreExec {
+    pub(crate) detach: Detach,
+    /// Existing process group joined instead of detaching, used by the
+    /// stages of a [`Pipeline`]
+    ///
+    /// [`Pipeline`]: crate::Pipeline
+    pub(crate) process_group: Option<u32>,
+    /// Whether the terminal connected to stdin becomes the controlling
+    /// terminal of the new session
+    pub(crate) controlling_terminal: bool,
+    pub(crate) rlimits: Vec<(Resource, Rlimit)>,
+    pub(crate) nice: Option<i32>,
+    #[cfg(target_os = "linux")]
+    pub(crate) sched_policy: Option<SchedPolicy>,
+    #[cfg(target_os = "linux")]
+    pub(crate) cpu_affinity: Option<Vec<usize>>,
+    #[cfg(target_os = "linux")]
+    pub(crate) io_priority: Option<IoPriority>,
+    #[cfg(target_os = "linux")]
+    pub(crate) oom_score_adj: Option<i32>,
+    pub(crate) credentials: Credentials,
+    pub(crate) umask: Option<libc::mode_t>,
+    #[cfg(target_os = "linux")]
+    pub(crate) no_new_privs: bool,
+}
+
+impl PreExec {
+    /// Detaches and configures the child process
+    pub(crate) fn apply(&self) -> io::Result<()> {
+        match self.process_group {
+            Some(pgid) => {
+                if unsafe { libc::setpgid(0, pgid as libc::pid_t) } < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            None => self.detach.apply()?,
+        }
+
+        if self.controlling_terminal {
+            pty::set_controlling_terminal()?;
+        }
+
+        self.configure()
+    }
+
+    /// Applies every setting but the detachment, which
+    /// [`Process::daemonize`] performs on its own
+    ///
+    /// [`Process::daemonize`]: crate::Process::daemonize
+    pub(crate) fn configure(&self) -> io::Result<()> {
+        for (resource, limit) in &self.rlimits {
+            resource.set(*limit)?;
+        }
+
+        #[cfg(target_os = "linux")]
+        if let Some(policy) = self.sched_policy {
+            sched::set_policy(0, policy)?;
+        }
+
+        if let Some(nice) = self.nice {
+            sched::set_nice(0, nice)?;
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            if let Some(ref cpus) = self.cpu_affinity {
+                sched::set_affinity(0, cpus)?;
+            }
+
+            if let Some(priority) = self.io_priority {
+                sched::set_io_priority(0, priority)?;
+            }
+
+            if let Some(score) = self.oom_score_adj {
+                sched::set_own_oom_score_adj(score)?;
+            }
+        }
+
+        // Privileges are dropped last, everything above may require them
+        self.credentials.apply()?;
+
+        if let Some(umask) = self.umask {
+            unsafe { libc::umask(umask) };
+        }
+
+        #[cfg(target_os = "linux")]
+        if self.no_new_privs && unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+}
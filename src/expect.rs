@@ -0,0 +1,609 @@
+use std::io::{self, Read, Write};
+use std::mem;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
+use std::process::{ChildStderr, ChildStdout};
+use std::time::{Duration, Instant};
+
+use crate::pattern::{Scratch, Searched};
+use crate::{Error, Pattern, Process, Pty, Result};
+
+/// Output of a [`Process`] matched by [`Interaction::expect`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Match {
+    before: String,
+    groups: Vec<Option<String>>,
+}
+
+impl Match {
+    /// Returns the text matched by the whole pattern
+    pub fn as_str(&self) -> &str {
+        self.get(0).unwrap_or_default()
+    }
+
+    /// Returns the output skipped before the match
+    pub fn before(&self) -> &str {
+        &self.before
+    }
+
+    /// Returns the text matched by the capture group at `index`, `0` being
+    /// the whole pattern
+    ///
+    /// Returns [`None`] if the group does not exist or did not participate
+    /// in the match.
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.groups.get(index)?.as_deref()
+    }
+}
+
+/// Upper bound on the output read by [`Interaction::fill`] before searching
+/// it again, in case the process writes faster than it is read
+const MAX_FILL_LEN: usize = 1 << 20;
+
+/// Upper bound on the unconsumed output kept by an [`Interaction`], older
+/// output is discarded once a search did not match it
+const MAX_BUFFER_LEN: usize = 1 << 20;
+
+/// Stream of the process read by an [`Interaction`]
+#[derive(Debug)]
+enum Stream {
+    Terminal,
+    Stdout(ChildStdout),
+    Stderr(ChildStderr),
+}
+
+/// Expect-style scripted interaction with a running [`Process`]
+///
+/// Reads the output of a process spawned with [`ProcessBuilder::pty`], or
+/// its stdout and stderr pipes, waiting for prompts with
+/// [`Interaction::expect`] and answering them with
+/// [`Interaction::send_line`]. Output is decoded as UTF-8, replacing
+/// invalid sequences with `U+FFFD`.
+///
+/// Output is matched as soon as it arrives, so a pattern such as `\d+` may
+/// match a number the process is still writing. Anchor patterns on the
+/// text following what you are interested in, e.g. `(\d+)\r?\n`.
+///
+/// Only the last MiB of output which was not consumed by a match is kept,
+/// anything older is discarded as if skipped by a match. This bounds the
+/// memory used while waiting for a pattern and the work done by every
+/// search, even for patterns such as `(?:.|\n)*done` which have to look at
+/// the whole output again whenever more of it arrives. The
+/// [`Interaction::transcript`] still records everything.
+///
+/// # Example
+///
+/// ```ignore
+/// use std::time::Duration;
+///
+/// use xprocess::{Interaction, Pattern, ProcessBuilder, WindowSize};
+///
+/// let process = ProcessBuilder::new("./install.sh").pty(WindowSize::default()).spawn().expect("Failed to spawn");
+/// let mut installer = Interaction::new(process).expect("No output to interact with");
+/// let timeout = Duration::from_secs(10);
+///
+/// installer.expect(&Pattern::literal("Install to [/opt]? "), timeout).expect("No prompt");
+/// installer.send_line("/usr/local").expect("Failed to answer");
+///
+/// let done = Pattern::new(r"Installed (\d+) files").expect("Invalid pattern");
+/// let found = installer.expect(&done, Duration::from_secs(60)).expect("Installation failed");
+/// println!("{} files", &found.get(1).unwrap());
+///
+/// installer.expect_eof(timeout).expect("Installer did not exit");
+/// print!("{}", installer.transcript());
+/// ```
+///
+/// [`ProcessBuilder::pty`]: crate::ProcessBuilder::pty
+#[derive(Debug)]
+pub struct Interaction {
+    process: Process,
+    pty: Option<Pty>,
+    /// Streams which did not reach end of file yet
+    streams: Vec<Stream>,
+    /// Output received but not consumed by a match yet
+    buffer: String,
+    /// Trailing bytes of a UTF-8 sequence split between two reads
+    pending: Vec<u8>,
+    transcript: String,
+    /// Buffers reused by every search of the output
+    scratch: Scratch,
+}
+
+impl Interaction {
+    /// Starts interacting with `process`, taking over its terminal or its
+    /// stdout and stderr pipes
+    ///
+    /// Fails with [`Error::StreamUnavailable`] if the process has neither a
+    /// terminal nor piped output.
+    pub fn new(mut process: Process) -> Result<Self> {
+        let pty = process.take_pty();
+        let streams = match pty {
+            Some(_) => vec![Stream::Terminal],
+            None => process
+                .take_stdout()
+                .map(Stream::Stdout)
+                .into_iter()
+                .chain(process.take_stderr().map(Stream::Stderr))
+                .collect(),
+        };
+
+        if streams.is_empty() {
+            return Err(Error::StreamUnavailable {
+                pid: process.pid(),
+                stream: "stdout",
+            });
+        }
+
+        Ok(Self {
+            process,
+            pty,
+            streams,
+            buffer: String::new(),
+            pending: Vec::new(),
+            transcript: String::new(),
+            scratch: Scratch::default(),
+        })
+    }
+
+    /// Waits up to `timeout` for the output of the process to match
+    /// `pattern`
+    ///
+    /// The matched text and everything before it is consumed, so the next
+    /// call only looks at the output following the match. The text skipped
+    /// before the match is limited to the last MiB of output, see
+    /// [`Interaction`].
+    ///
+    /// Fails with [`Error::Timeout`] if nothing matched in time and with
+    /// [`Error::UnexpectedEof`] if the process closed its output first.
+    pub fn expect(&mut self, pattern: &Pattern, timeout: Duration) -> Result<Match> {
+        let deadline = Instant::now() + timeout;
+        // Only the output which was just received is searched again, along
+        // with the end of a partial match
+        let mut from = 0;
+
+        loop {
+            match pattern.search_from(&self.buffer, from, &mut self.scratch) {
+                Searched::Match(groups) => {
+                    let (start, end) = groups[0].expect("The whole pattern always matches");
+                    let groups = groups
+                        .into_iter()
+                        .map(|group| group.map(|(start, end)| self.buffer[start..end].to_owned()))
+                        .collect();
+                    let before = self.buffer[..start].to_owned();
+                    self.buffer.drain(..end);
+
+                    return Ok(Match { before, groups });
+                }
+                Searched::NoMatch { resume } => from = resume,
+            }
+
+            if self.streams.is_empty() {
+                return Err(Error::UnexpectedEof {
+                    pid: self.process.pid(),
+                    expected: pattern.as_str().to_owned(),
+                });
+            }
+
+            if !self.fill(deadline)? {
+                return Err(Error::Timeout {
+                    pid: self.process.pid(),
+                    expected: pattern.as_str().to_owned(),
+                });
+            }
+
+            from = from.saturating_sub(self.trim_buffer());
+        }
+    }
+
+    /// Waits up to `timeout` for the process to close its output and
+    /// returns everything which was not consumed yet, up to the last MiB
+    ///
+    /// Fails with [`Error::Timeout`] if the output is still open. The
+    /// process is not reaped, use [`Process::wait`] through
+    /// [`Interaction::process_mut`].
+    pub fn expect_eof(&mut self, timeout: Duration) -> Result<String> {
+        let deadline = Instant::now() + timeout;
+
+        while !self.streams.is_empty() {
+            if !self.fill(deadline)? {
+                return Err(Error::Timeout {
+                    pid: self.process.pid(),
+                    expected: "end of output".to_owned(),
+                });
+            }
+
+            self.trim_buffer();
+        }
+
+        Ok(mem::take(&mut self.buffer))
+    }
+
+    /// Writes `data` to the terminal or to the stdin pipe of the process
+    ///
+    /// Fails with [`Error::StreamUnavailable`] if stdin is not piped.
+    pub fn send<D: AsRef<[u8]>>(&mut self, data: D) -> Result<()> {
+        if let Some(ref pty) = self.pty {
+            return Ok((&*pty).write_all(data.as_ref())?);
+        }
+
+        let pid = self.process.pid();
+        let stdin = self.process.stdin().ok_or(Error::StreamUnavailable {
+            pid,
+            stream: "stdin",
+        })?;
+        stdin.write_all(data.as_ref())?;
+        Ok(stdin.flush()?)
+    }
+
+    /// Writes `line` followed by a newline, as if typed and submitted
+    pub fn send_line(&mut self, line: &str) -> Result<()> {
+        self.send(format!("{line}\n"))
+    }
+
+    /// Signals the end of input
+    ///
+    /// Closes the stdin pipe, or types the end of file character (`Ctrl-D`)
+    /// in the terminal, which only takes effect at the start of a line.
+    pub fn send_eof(&mut self) -> Result<()> {
+        if self.pty.is_some() {
+            return self.send([0x04]);
+        }
+
+        self.process.close_stdin();
+        Ok(())
+    }
+
+    /// Returns everything the process wrote so far, consumed or not
+    ///
+    /// Terminals echo their input, so the transcript of a process spawned
+    /// with [`ProcessBuilder::pty`] includes what was sent to it as well.
+    ///
+    /// [`ProcessBuilder::pty`]: crate::ProcessBuilder::pty
+    pub fn transcript(&self) -> &str {
+        &self.transcript
+    }
+
+    /// Returns the process being interacted with
+    pub fn process(&self) -> &Process {
+        &self.process
+    }
+
+    /// Returns the process being interacted with, e.g. to wait for it
+    pub fn process_mut(&mut self) -> &mut Process {
+        &mut self.process
+    }
+
+    /// Ends the interaction and returns the process along with its
+    /// terminal or pipes
+    ///
+    /// Output which was received but not consumed is discarded.
+    pub fn into_process(mut self) -> Process {
+        if let Some(pty) = self.pty.take() {
+            self.process.set_pty(pty);
+        }
+
+        for stream in mem::take(&mut self.streams) {
+            match stream {
+                Stream::Terminal => {}
+                Stream::Stdout(stdout) => self.process.set_stdout(stdout),
+                Stream::Stderr(stderr) => self.process.set_stderr(stderr),
+            }
+        }
+
+        self.process
+    }
+
+    fn stream_fd<'a>(&'a self, stream: &'a Stream) -> BorrowedFd<'a> {
+        match stream {
+            Stream::Terminal => self
+                .pty
+                .as_ref()
+                .expect("Terminal streams have a pty")
+                .as_fd(),
+            Stream::Stdout(stdout) => stdout.as_fd(),
+            Stream::Stderr(stderr) => stderr.as_fd(),
+        }
+    }
+
+    /// Waits until `deadline` for output and reads everything available into
+    /// the buffer
+    ///
+    /// Returns `false` once the deadline passed without any stream becoming
+    /// readable.
+    fn fill(&mut self, deadline: Instant) -> Result<bool> {
+        let mut timeout_ms = deadline
+            .saturating_duration_since(Instant::now())
+            .as_micros()
+            .div_ceil(1000)
+            .min(libc::c_int::MAX as u128) as libc::c_int;
+        let mut buf = vec![0; 64 * 1024];
+        let mut filled = 0;
+        let mut waited = false;
+
+        // Keep reading without waiting until the streams are drained, so the
+        // output is searched once rather than after every read
+        while !self.streams.is_empty() && filled < MAX_FILL_LEN {
+            let mut pollfds = self
+                .streams
+                .iter()
+                .map(|stream| libc::pollfd {
+                    fd: self.stream_fd(stream).as_raw_fd(),
+                    events: libc::POLLIN,
+                    revents: 0,
+                })
+                .collect::<Vec<_>>();
+
+            match unsafe {
+                libc::poll(
+                    pollfds.as_mut_ptr(),
+                    pollfds.len() as libc::nfds_t,
+                    timeout_ms,
+                )
+            } {
+                -1 => {
+                    let err = io::Error::last_os_error();
+
+                    if err.kind() == io::ErrorKind::Interrupted {
+                        break;
+                    }
+
+                    return Err(err.into());
+                }
+                0 if !waited => return Ok(Instant::now() < deadline),
+                0 => break,
+                _ => {}
+            }
+
+            // Iterate backwards so finished streams can be removed in place
+            for index in (0..pollfds.len()).rev() {
+                if pollfds[index].revents == 0 {
+                    continue;
+                }
+
+                let read = match self.streams[index] {
+                    Stream::Terminal => self
+                        .pty
+                        .as_mut()
+                        .expect("Terminal streams have a pty")
+                        .read(&mut buf),
+                    Stream::Stdout(ref mut stdout) => stdout.read(&mut buf),
+                    Stream::Stderr(ref mut stderr) => stderr.read(&mut buf),
+                };
+
+                match read {
+                    Ok(0) => {
+                        self.streams.remove(index);
+                    }
+                    Ok(len) => {
+                        self.push(&buf[..len]);
+                        filled += len;
+                    }
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                    Err(err) => return Err(err.into()),
+                }
+            }
+
+            waited = true;
+            timeout_ms = 0;
+        }
+
+        if self.streams.is_empty() && !self.pending.is_empty() {
+            // The output ended in the middle of a UTF-8 sequence
+            let text = String::from_utf8_lossy(&self.pending).into_owned();
+            self.pending.clear();
+            self.append(&text);
+        }
+
+        Ok(true)
+    }
+
+    /// Discards the oldest output beyond [`MAX_BUFFER_LEN`] and returns the
+    /// number of bytes discarded
+    fn trim_buffer(&mut self) -> usize {
+        let mut excess = self.buffer.len().saturating_sub(MAX_BUFFER_LEN);
+
+        while !self.buffer.is_char_boundary(excess) {
+            excess += 1;
+        }
+
+        self.buffer.drain(..excess);
+        excess
+    }
+
+    /// Decodes `data` and appends it to the buffer and the transcript,
+    /// keeping back a trailing incomplete UTF-8 sequence
+    fn push(&mut self, data: &[u8]) {
+        self.pending.extend_from_slice(data);
+        let chunks = self.pending.utf8_chunks().collect::<Vec<_>>();
+        let mut text = String::with_capacity(self.pending.len());
+        let mut incomplete = 0;
+
+        for (index, chunk) in chunks.iter().enumerate() {
+            text.push_str(chunk.valid());
+
+            if chunk.invalid().is_empty() {
+                continue;
+            }
+
+            // Only the last sequence can be cut short by the end of a read
+            let cut_short = index == chunks.len() - 1
+                && std::str::from_utf8(chunk.invalid()).is_err_and(|err| err.error_len().is_none());
+
+            if cut_short {
+                incomplete = chunk.invalid().len();
+            } else {
+                text.push(char::REPLACEMENT_CHARACTER);
+            }
+        }
+
+        self.pending.drain(..self.pending.len() - incomplete);
+        self.append(&text);
+    }
+
+    fn append(&mut self, text: &str) {
+        self.buffer.push_str(text);
+        self.transcript.push_str(text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ProcessBuilder, StdioMode, WindowSize};
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn pattern(pattern: &str) -> Pattern {
+        Pattern::new(pattern).expect("Failed to compile pattern")
+    }
+
+    #[test]
+    fn interacts_through_pipes() {
+        let process = ProcessBuilder::new("sh")
+            .args([
+                "-c",
+                "printf 'Name? '; read name; echo \"Hello, $name!\"; read age; echo \"$name is $age\" >&2",
+            ])
+            .stdin(StdioMode::Piped)
+            .spawn()
+            .expect("Failed to spawn process");
+        let mut interaction = Interaction::new(process).expect("Failed to interact");
+
+        let found = interaction
+            .expect(&Pattern::literal("Name? "), TIMEOUT)
+            .expect("Missing prompt");
+        assert_eq!(found.before(), "");
+        interaction.send_line("Ada").expect("Failed to send name");
+
+        let found = interaction
+            .expect(&pattern(r"Hello, (\w+)!\n"), TIMEOUT)
+            .expect("Missing greeting");
+        assert_eq!(found.get(1), Some("Ada"));
+        interaction.send_line("36").expect("Failed to send age");
+        interaction.send_eof().expect("Failed to close stdin");
+
+        // stderr is read as well
+        let found = interaction
+            .expect(&pattern(r"(\w+) is (\d+)\n"), TIMEOUT)
+            .expect("Missing summary");
+        assert_eq!(found.as_str(), "Ada is 36\n");
+        assert_eq!(found.get(3), None);
+        assert_eq!(interaction.expect_eof(TIMEOUT).unwrap(), "");
+        assert_eq!(interaction.transcript(), "Name? Hello, Ada!\nAda is 36\n");
+
+        let mut process = interaction.into_process();
+        assert!(process.wait().expect("Failed to wait").success());
+    }
+
+    #[test]
+    fn interacts_through_pty() {
+        let process = ProcessBuilder::new("sh")
+            .args(["-c", "printf '> '; read cmd; echo \"ran $cmd\"; cat"])
+            .pty(WindowSize::default())
+            .spawn()
+            .expect("Failed to spawn process");
+        let mut interaction = Interaction::new(process).expect("Failed to interact");
+
+        interaction
+            .expect(&pattern("> $"), TIMEOUT)
+            .expect("Missing prompt");
+        interaction.send_line("ls").expect("Failed to send command");
+        let found = interaction
+            .expect(&pattern(r"ran (\w+)\r\n"), TIMEOUT)
+            .expect("Missing output");
+        // The terminal echoes the input
+        assert_eq!(found.before(), "ls\r\n");
+        assert_eq!(found.get(1), Some("ls"));
+
+        interaction.send_eof().expect("Failed to send EOF");
+        interaction.expect_eof(TIMEOUT).expect("Missing EOF");
+        assert!(interaction.transcript().starts_with("> ls\r\nran ls\r\n"));
+        assert!(
+            interaction
+                .process_mut()
+                .wait()
+                .expect("Failed to wait")
+                .success()
+        );
+    }
+
+    #[test]
+    fn reports_timeout_and_eof() {
+        let process = ProcessBuilder::new("sh")
+            .args(["-c", "echo ready; sleep 5"])
+            .kill_on_drop(true)
+            .spawn()
+            .expect("Failed to spawn process");
+        let mut interaction = Interaction::new(process).expect("Failed to interact");
+        let err = interaction
+            .expect(&pattern("never"), Duration::from_millis(100))
+            .expect_err("Pattern should not match");
+        assert!(matches!(err, Error::Timeout { ref expected, .. } if expected == "never"));
+        // Output read while waiting is still available
+        interaction
+            .expect(&pattern("^ready\n"), TIMEOUT)
+            .expect("Missing output");
+
+        let process = ProcessBuilder::new("echo")
+            .arg("done")
+            .spawn()
+            .expect("Failed to spawn process");
+        let mut interaction = Interaction::new(process).expect("Failed to interact");
+        let err = interaction
+            .expect(&pattern("never"), TIMEOUT)
+            .expect_err("Pattern should not match");
+        assert!(matches!(err, Error::UnexpectedEof { .. }));
+        assert_eq!(interaction.transcript(), "done\n");
+    }
+
+    #[test]
+    fn matches_after_large_output() {
+        let process = ProcessBuilder::new("sh")
+            .args([
+                "-c",
+                "yes 'Copying file to destination' | head -n 150000; echo 'Installed 3 files'",
+            ])
+            .spawn()
+            .expect("Failed to spawn process");
+        let mut interaction = Interaction::new(process).expect("Failed to interact");
+
+        let found = interaction
+            .expect(&pattern(r"Installed (\d+) files"), Duration::from_secs(10))
+            .expect("Missing summary");
+        assert_eq!(found.get(1), Some("3"));
+        // Only the most recent output is kept
+        assert!(found.before().len() <= MAX_BUFFER_LEN);
+        assert!(found.before().ends_with("Copying file to destination\n"));
+        interaction.expect_eof(TIMEOUT).expect("Missing EOF");
+        assert_eq!(interaction.transcript().len(), 150_000 * 28 + 18);
+    }
+
+    #[test]
+    fn bounds_unmatched_output() {
+        let process = ProcessBuilder::new("sh")
+            .args(["-c", "yes 'é' | head -n 1000000; echo done"])
+            .spawn()
+            .expect("Failed to spawn process");
+        let mut interaction = Interaction::new(process).expect("Failed to interact");
+
+        // The pattern has to search the whole buffer again after every read
+        let found = interaction
+            .expect(&pattern(r"(?:.|\n)*done"), Duration::from_secs(30))
+            .expect("Missing summary");
+        assert!(found.as_str().len() <= MAX_BUFFER_LEN);
+        assert!(found.as_str().ends_with("é\ndone"));
+        assert_eq!(interaction.transcript().len(), 3_000_000 + 5);
+    }
+
+    #[test]
+    fn decodes_split_utf8() {
+        let process = ProcessBuilder::new("true")
+            .spawn()
+            .expect("Failed to spawn process");
+        let mut interaction = Interaction::new(process).expect("Failed to interact");
+        let bytes = "héllo".as_bytes();
+        interaction.push(&bytes[..2]);
+        interaction.push(&bytes[2..]);
+        interaction.push(b"\xff!");
+        assert_eq!(interaction.transcript(), "héllo\u{fffd}!");
+    }
+}
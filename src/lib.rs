@@ -1,9 +1,62 @@
+mod async_io;
+#[cfg(feature = "tokio")]
+mod async_process;
+mod builder;
+mod daemon;
+mod detach;
+mod error;
+mod exec;
+mod expect;
+mod output;
+mod pattern;
+#[cfg(target_os = "linux")]
+mod pidfd;
+mod pipeline;
+mod pty;
+mod reactor;
+mod rlimit;
+mod rusage;
+mod sched;
+mod signal;
+#[cfg(target_os = "linux")]
+mod stats;
+mod status;
+mod stop;
+mod user;
+
 use std::ffi::OsStr;
-use std::io::Read;
+use std::io::{self, BufRead, BufReader, Lines, Read, Write};
+#[cfg(target_os = "linux")]
+use std::os::fd::{AsFd, BorrowedFd};
 use std::os::unix::process::CommandExt;
-use std::process::{Child, Command, Stdio};
+use std::process::{Child, ChildStderr, ChildStdin, ChildStdout, Command};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use exec::PreExec;
 
-use anyhow::{Result, bail};
+pub use async_io::{AsyncIo, timeout};
+#[cfg(feature = "tokio")]
+pub use async_process::AsyncProcess;
+pub use builder::{ProcessBuilder, StdioMode};
+pub use daemon::DaemonOptions;
+pub use detach::Detach;
+pub use error::{Error, Result};
+pub use expect::{Interaction, Match};
+pub use output::Output;
+pub use pattern::Pattern;
+pub use pipeline::{Pipeline, PipelineOutput, RunningPipeline};
+pub use pty::{Pty, WindowSize};
+pub use rlimit::{Resource, Rlimit};
+pub use rusage::ResourceUsage;
+#[cfg(target_os = "linux")]
+pub use sched::{IoPriority, SchedPolicy};
+pub use signal::Signal;
+#[cfg(target_os = "linux")]
+pub use stats::{ProcessState, ProcessStats};
+pub use status::ExitStatus;
+pub use stop::{StopPolicy, StopStep};
+pub use user::{Group, User};
 
 /// Reference of a system process spawned by [`Process::spawn`]
 ///
@@ -19,15 +72,32 @@ use anyhow::{Result, bail};
 /// }
 /// ```
 ///
+#[derive(Debug)]
 pub struct Process {
     pid: u32,
+    /// Process group created for the process when it was spawned
+    pgid: Option<u32>,
     child: Option<Child>,
+    /// Exit status collected once the process has been reaped
+    status: Option<ExitStatus>,
+    /// Resources consumed by the process, collected along its exit status
+    usage: Option<ResourceUsage>,
+    /// Background thread writing the buffer provided by
+    /// [`ProcessBuilder::stdin_bytes`]
+    stdin_feeder: Option<JoinHandle<io::Result<()>>>,
+    /// Whether the process group is killed when the handle is dropped
+    kill_on_drop: bool,
+    /// Handle used to signal and wait for the process without racing against
+    /// PID reuse, unavailable on kernels older than Linux 5.3
+    #[cfg(target_os = "linux")]
+    pidfd: Option<pidfd::PidFd>,
+    /// Pseudo-terminal allocated by [`ProcessBuilder::pty`]
+    pty: Option<Pty>,
 }
 
 impl Process {
     pub fn spawn<S: AsRef<OsStr>>(cmd: S) -> Result<Self> {
-        let mut command = Self::build_command::<S, _, S>(cmd, []);
-        Self::spawn_child_process(&mut command)
+        ProcessBuilder::new(cmd).spawn()
     }
 
     pub fn spawn_with_args<S, I, T>(cmd: S, args: I) -> Result<Self>
@@ -36,11 +106,45 @@ impl Process {
         I: IntoIterator<Item = T>,
         S: AsRef<OsStr>,
     {
-        let mut command = Self::build_command(cmd, args);
-        Self::spawn_child_process(&mut command)
+        ProcessBuilder::new(cmd).args(args).spawn()
+    }
+
+    /// Spawns the program configured by `builder` as a daemon and returns its
+    /// PID
+    ///
+    /// The program is started through a double fork: it runs in a new
+    /// session it does not lead, with `/` as working directory unless
+    /// [`ProcessBuilder::current_dir`] is set, and is reparented to `init` so
+    /// it survives the current process. Standard streams configured as piped
+    /// or inherited are redirected to `/dev/null`, use [`StdioMode::File`] to
+    /// keep logs.
+    ///
+    /// When [`DaemonOptions::pidfile`] is set, the PID is written to a pidfile
+    /// locked for the lifetime of the daemon, spawning a second daemon with
+    /// the same pidfile fails with [`Error::PidfileLocked`].
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use xprocess::{DaemonOptions, Process, ProcessBuilder};
+    ///
+    /// let pid = Process::daemonize(
+    ///     ProcessBuilder::new("my-server").arg("--port=8080"),
+    ///     &DaemonOptions::new().pidfile("/run/my-server.pid"),
+    /// )
+    /// .expect("Failed to start daemon");
+    /// ```
+    pub fn daemonize(builder: &ProcessBuilder, options: &DaemonOptions) -> Result<u32> {
+        daemon::daemonize(builder, options)
     }
 
-    fn build_command<S, I, T>(cmd: S, args: I) -> Command
+    /// Creates a [`ProcessBuilder`] to configure the environment, working
+    /// directory and standard streams of the process before spawning it
+    pub fn builder<S: AsRef<OsStr>>(cmd: S) -> ProcessBuilder {
+        ProcessBuilder::new(cmd)
+    }
+
+    pub(crate) fn build_command<S, I, T>(cmd: S, args: I) -> Command
     where
         T: AsRef<OsStr>,
         I: IntoIterator<Item = T>,
@@ -51,39 +155,327 @@ impl Process {
         command
     }
 
-    fn spawn_child_process(cmd: &mut Command) -> Result<Self> {
-        let mut child = cmd
-            .stdin(Stdio::null())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
+    pub(crate) fn spawn_child_process(cmd: &mut Command, pre_exec: PreExec) -> Result<Self> {
+        let mut child = cmd;
+        let detach = pre_exec.detach;
+        let process_group = pre_exec.process_group;
 
         unsafe {
-            child = child.pre_exec(|| {
-                // Create a new session to detach the process
-                libc::setsid();
-                Ok(())
+            child = child.pre_exec(move || {
+                // Detach the process, by default into a new session, then
+                // apply limits and other settings
+                pre_exec.apply()
             });
         }
 
-        let child_process = child.spawn()?;
+        let child_process = child.spawn().map_err(|err| Error::spawn(child, err))?;
         let pid = child_process.id();
 
+        // The child can not be reaped, and its PID reused, before this call
+        // since only this process can wait for it
+        #[cfg(target_os = "linux")]
+        let pidfd = pidfd::PidFd::open(pid).ok();
+
         Ok(Self {
             pid,
+            pgid: match process_group {
+                Some(pgid) => Some(pgid),
+                // Both `setsid` and `setpgid` make the child the leader of a
+                // new process group
+                None => detach.new_process_group().then_some(pid),
+            },
             child: Some(child_process),
+            status: None,
+            usage: None,
+            stdin_feeder: None,
+            kill_on_drop: false,
+            #[cfg(target_os = "linux")]
+            pidfd,
+            pty: None,
+        })
+    }
+
+    /// Attaches to a running process which was not spawned by this handle,
+    /// e.g. a daemon found through its pidfile
+    ///
+    /// The returned [`Process`] supports signalling, liveness checks and
+    /// waiting for the process to exit. On Linux waiting relies on a pidfd
+    /// obtained with `pidfd_open(2)`, on other platforms and older kernels
+    /// the process is polled.
+    ///
+    /// **Important:** Only the parent of a process can collect its exit status,
+    /// so waiting on a process which is not a child of the current process returns
+    /// [`ExitStatus::Unavailable`]. Its output can not be captured either.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use xprocess::Process;
+    ///
+    /// let pid = std::fs::read_to_string("/run/my-server.pid").expect("Failed to read pidfile");
+    /// let mut process = Process::from_pid(pid.trim().parse().unwrap()).expect("Failed to attach");
+    /// process.terminate().expect("Failed to terminate process");
+    /// process.wait().expect("Failed to wait for process");
+    /// ```
+    pub fn from_pid(pid: u32) -> Result<Self> {
+        if pid == 0 || pid > libc::pid_t::MAX as u32 {
+            return Err(Error::NoSuchProcess { pid });
+        }
+
+        if !Self::exists(pid)? {
+            return Err(Error::NoSuchProcess { pid });
+        }
+
+        #[cfg(target_os = "linux")]
+        let pidfd = match pidfd::PidFd::open(pid) {
+            Ok(pidfd) => Some(pidfd),
+            Err(err) if err.raw_os_error() == Some(libc::ESRCH) => {
+                return Err(Error::NoSuchProcess { pid });
+            }
+            // Kernels older than 5.3 lack `pidfd_open`, fall back to polling
+            Err(_) => None,
+        };
+
+        let pgid = unsafe { libc::getpgid(pid as libc::pid_t) };
+
+        Ok(Self {
+            pid,
+            pgid: (pgid as u32 == pid).then_some(pid),
+            child: None,
+            status: None,
+            usage: None,
+            stdin_feeder: None,
+            kill_on_drop: false,
+            #[cfg(target_os = "linux")]
+            pidfd,
+            pty: None,
         })
     }
 
+    /// Checks whether a process with the provided PID exists
+    fn exists(pid: u32) -> Result<bool> {
+        if unsafe { libc::kill(pid as libc::pid_t, 0) } == 0 {
+            return Ok(true);
+        }
+
+        match Error::last_os_error(pid, "check") {
+            Error::NoSuchProcess { .. } => Ok(false),
+            // The process exists but belongs to a different user
+            Error::NotPermitted { .. } => Ok(true),
+            err => Err(err),
+        }
+    }
+
     /// Retrieves PID for the spawned process
     pub fn pid(&self) -> u32 {
         self.pid
     }
 
+    /// Returns the pidfd referring to the process, which becomes readable
+    /// once the process exits
+    ///
+    /// Lets async runtimes wait for the process through their own reactor.
+    /// Returns [`None`] on kernels older than Linux 5.3.
+    #[cfg(target_os = "linux")]
+    pub fn pidfd(&self) -> Option<BorrowedFd<'_>> {
+        self.pidfd.as_ref().map(AsFd::as_fd)
+    }
+
+    /// Configures whether the process group is killed when this handle is
+    /// dropped
+    ///
+    /// See [`ProcessBuilder::kill_on_drop`] for details.
+    pub fn set_kill_on_drop(&mut self, kill_on_drop: bool) {
+        self.kill_on_drop = kill_on_drop;
+    }
+
+    /// Releases the process so it keeps running after this handle is dropped
+    ///
+    /// The process is neither killed nor reaped by this crate anymore and its
+    /// PID is returned. Pipes owned by the handle are closed, so a detached
+    /// process writing to a piped stdout or stderr receives `SIGPIPE`;
+    /// redirect its streams with [`ProcessBuilder`] instead.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use xprocess::{ProcessBuilder, StdioMode};
+    ///
+    /// let pid = ProcessBuilder::new("my-server")
+    ///     .stdout(StdioMode::Null)
+    ///     .stderr(StdioMode::Null)
+    ///     .kill_on_drop(true)
+    ///     .spawn()
+    ///     .expect("Failed to spawn")
+    ///     .detach();
+    /// ```
+    pub fn detach(mut self) -> u32 {
+        self.kill_on_drop = false;
+        drop(self.child.take());
+        self.pid
+    }
+
+    /// Retrieves the process group ID of the process
+    ///
+    /// Unless spawned with [`Detach::None`], processes lead their own process
+    /// group, so this is usually the same as [`Process::pid`].
+    ///
+    /// Fails with [`Error::NoSuchProcess`] once the process was reaped.
+    pub fn pgid(&self) -> Result<u32> {
+        let pgid = unsafe { libc::getpgid(self.running_pid()?) };
+
+        if pgid < 0 {
+            return Err(Error::last_os_error(self.pid, "query process group of"));
+        }
+
+        Ok(pgid as u32)
+    }
+
+    /// Retrieves the session ID of the process
+    ///
+    /// Processes spawned with [`Detach::Session`], the default, lead their
+    /// own session, so this is usually the same as [`Process::pid`].
+    ///
+    /// Fails with [`Error::NoSuchProcess`] once the process was reaped.
+    pub fn sid(&self) -> Result<u32> {
+        let sid = unsafe { libc::getsid(self.running_pid()?) };
+
+        if sid < 0 {
+            return Err(Error::last_os_error(self.pid, "query session of"));
+        }
+
+        Ok(sid as u32)
+    }
+
+    /// Retrieves a writer for the stdin pipe of the process
+    ///
+    /// Returns [`None`] unless stdin was configured with [`StdioMode::Piped`],
+    /// or if it was already closed with [`Process::close_stdin`].
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use std::io::Write;
+    ///
+    /// use xprocess::{ProcessBuilder, StdioMode};
+    ///
+    /// let mut process = ProcessBuilder::new("sort").stdin(StdioMode::Piped).spawn().expect("Failed to spawn");
+    /// let stdin = process.stdin().expect("Stdin is not piped");
+    /// stdin.write_all(b"b\na\n").expect("Failed to write to stdin");
+    /// process.close_stdin();
+    /// assert_eq!(process.stdout().expect("Failed to read stdout"), "a\nb\n");
+    /// ```
+    pub fn stdin(&mut self) -> Option<&mut ChildStdin> {
+        self.child.as_mut().and_then(|child| child.stdin.as_mut())
+    }
+
+    /// Closes the stdin pipe of the process, signaling the end of its input
+    pub fn close_stdin(&mut self) {
+        if let Some(ref mut child) = self.child {
+            drop(child.stdin.take());
+        }
+    }
+
+    /// Takes ownership of the stdin pipe of the process
+    ///
+    /// Returns [`None`] if stdin is not piped or was already taken.
+    pub fn take_stdin(&mut self) -> Option<ChildStdin> {
+        self.child.as_mut().and_then(|child| child.stdin.take())
+    }
+
+    /// Returns the pseudo-terminal of a process spawned with
+    /// [`ProcessBuilder::pty`]
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use std::io::{Read, Write};
+    ///
+    /// use xprocess::{ProcessBuilder, WindowSize};
+    ///
+    /// let mut process = ProcessBuilder::new("python3").pty(WindowSize::default()).spawn().expect("Failed to spawn");
+    /// let pty = process.pty().expect("No terminal allocated");
+    /// pty.write_all(b"print(6 * 7)\nexit()\n").expect("Failed to write to terminal");
+    ///
+    /// let mut transcript = String::new();
+    /// pty.read_to_string(&mut transcript).expect("Failed to read terminal");
+    /// ```
+    pub fn pty(&mut self) -> Option<&mut Pty> {
+        self.pty.as_mut()
+    }
+
+    /// Attaches the pseudo-terminal allocated by [`ProcessBuilder::pty`]
+    pub(crate) fn set_pty(&mut self, pty: Pty) {
+        self.pty = Some(pty);
+    }
+
+    /// Takes ownership of the pseudo-terminal of the process, e.g. to read
+    /// and write from separate threads
+    pub fn take_pty(&mut self) -> Option<Pty> {
+        self.pty.take()
+    }
+
+    /// Resizes the pseudo-terminal of the process, which receives
+    /// [`Signal::Winch`]
+    ///
+    /// Fails with [`Error::StreamUnavailable`] if the process was not spawned
+    /// with a terminal or it was taken with [`Process::take_pty`].
+    pub fn set_window_size(&self, size: WindowSize) -> Result<()> {
+        let pty = self.pty.as_ref().ok_or(Error::StreamUnavailable {
+            pid: self.pid,
+            stream: "terminal",
+        })?;
+
+        Ok(pty.set_window_size(size)?)
+    }
+
+    /// Writes `data` to the stdin pipe of the process on a background thread,
+    /// closing the pipe once everything was written
+    ///
+    /// Feeding stdin from a separate thread prevents deadlocks against a
+    /// process which fills up its output pipes before consuming its input.
+    pub(crate) fn feed_stdin(&mut self, data: Vec<u8>) {
+        let Some(mut stdin) = self.child.as_mut().and_then(|child| child.stdin.take()) else {
+            return;
+        };
+
+        self.stdin_feeder = Some(thread::spawn(move || {
+            match stdin.write_all(&data) {
+                // The process is not required to consume its whole input
+                Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+                result => result,
+            }
+        }));
+    }
+
+    /// Joins the thread started by [`Process::feed_stdin`] once the process
+    /// was reaped and reports its errors
+    ///
+    /// A thread which is still writing is detached instead: a descendant of
+    /// the process may hold stdin open long after the process exited, the
+    /// thread then ends once the descendant closes the pipe.
+    fn join_stdin_feeder(&mut self) -> Result<()> {
+        match self.stdin_feeder.take() {
+            Some(feeder) if feeder.is_finished() => {
+                feeder
+                    .join()
+                    .map_err(|_| io::Error::other("Stdin writer thread panicked"))??;
+            }
+            // Dropping the handle detaches the thread
+            _ => {}
+        }
+
+        Ok(())
+    }
+
     /// Reads and returns the stdout of the process
     ///
     /// This method reads all available output from stdout and returns it as a String.
     /// The method will block until the process closes its stdout stream.
     ///
+    /// Fails if the output is not valid UTF-8, use [`Process::stdout_bytes`] or
+    /// [`Process::stdout_lossy`] for processes writing arbitrary bytes.
+    ///
     /// **Important:** This method consumes the stdout handle. Subsequent calls will return
     /// an empty String.
     ///
@@ -91,6 +483,10 @@ impl Process {
     /// waiting for the process to finish or close stdout before calling this method,
     /// otherwise it may block indefinitely.
     ///
+    /// **Note:** Reading one stream to completion while the process fills up the pipe
+    /// of the other one can deadlock. Prefer [`Process::wait_with_output`] to run a
+    /// process to completion.
+    ///
     /// # Example
     ///
     /// ```ignore
@@ -104,9 +500,13 @@ impl Process {
         if let Some(ref mut child) = self.child
             && let Some(ref mut stdout) = child.stdout
         {
-            let mut output = String::new();
-            stdout.read_to_string(&mut output)?;
-            return Ok(output);
+            let mut output = Vec::new();
+            stdout.read_to_end(&mut output)?;
+            return String::from_utf8(output).map_err(|source| Error::NotUtf8 {
+                pid: self.pid,
+                stream: "stdout",
+                source,
+            });
         }
         Ok(String::new())
     }
@@ -116,6 +516,9 @@ impl Process {
     /// This method reads all available output from stderr and returns it as a String.
     /// The method will block until the process closes its stderr stream.
     ///
+    /// Fails if the output is not valid UTF-8, use [`Process::stderr_bytes`] or
+    /// [`Process::stderr_lossy`] for processes writing arbitrary bytes.
+    ///
     /// **Important:** This method consumes the stderr handle. Subsequent calls will return
     /// an empty String.
     ///
@@ -123,6 +526,10 @@ impl Process {
     /// waiting for the process to finish or close stderr before calling this method,
     /// otherwise it may block indefinitely.
     ///
+    /// **Note:** Reading one stream to completion while the process fills up the pipe
+    /// of the other one can deadlock. Prefer [`Process::wait_with_output`] to run a
+    /// process to completion.
+    ///
     /// # Example
     ///
     /// ```ignore
@@ -136,105 +543,1338 @@ impl Process {
         if let Some(ref mut child) = self.child
             && let Some(ref mut stderr) = child.stderr
         {
-            let mut output = String::new();
-            stderr.read_to_string(&mut output)?;
-            return Ok(output);
+            let mut output = Vec::new();
+            stderr.read_to_end(&mut output)?;
+            return String::from_utf8(output).map_err(|source| Error::NotUtf8 {
+                pid: self.pid,
+                stream: "stderr",
+                source,
+            });
         }
         Ok(String::new())
     }
 
-    /// Kills the process referenced by this instance of [`Process`]
-    pub fn kill(&self) -> Result<()> {
-        match Command::new("kill").arg(self.pid().to_string()).status() {
-            Ok(status) => {
-                if status.success() {
-                    return Ok(());
-                }
+    /// Reads and returns the stdout of the process as raw bytes
+    ///
+    /// This behaves like [`Process::stdout`] without requiring the output to
+    /// be valid UTF-8, which makes it suitable for tools such as `tar` or
+    /// `gzip`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut process = Process::spawn_with_args("gzip", ["-c", "/etc/hosts"]).expect("Failed to spawn");
+    /// let compressed = process.stdout_bytes().expect("Failed to read stdout");
+    /// assert_eq!(&compressed[..2], [0x1f, 0x8b]);
+    /// ```
+    pub fn stdout_bytes(&mut self) -> Result<Vec<u8>> {
+        let mut output = Vec::new();
 
-                bail!("Failed to kill process with PID: {}", self.pid());
-            }
-            Err(e) => {
-                bail!("Error executing kill command: {}", e);
-            }
+        if let Some(ref mut child) = self.child
+            && let Some(ref mut stdout) = child.stdout
+        {
+            stdout.read_to_end(&mut output)?;
         }
+
+        Ok(output)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::thread;
-    use std::time::Duration;
+    /// Reads and returns the stderr of the process as raw bytes
+    ///
+    /// See [`Process::stdout_bytes`] for details.
+    pub fn stderr_bytes(&mut self) -> Result<Vec<u8>> {
+        let mut output = Vec::new();
 
-    use super::*;
+        if let Some(ref mut child) = self.child
+            && let Some(ref mut stderr) = child.stderr
+        {
+            stderr.read_to_end(&mut output)?;
+        }
 
-    #[test]
-    fn spawn_process() {
-        let process = Process::spawn("sleep").expect("Failed to spawn process");
-        assert!(process.pid() > 0);
-        thread::sleep(Duration::from_millis(100));
-        let result = process.kill();
-        assert!(result.is_ok(), "Failed to kill the process");
+        Ok(output)
     }
 
-    #[test]
-    fn spawn_process_with_args() {
-        let process = Process::spawn_with_args("sleep", ["1"]).expect("Failed to spawn process");
-        assert!(process.pid() > 0);
-        thread::sleep(Duration::from_millis(100));
-        let result = process.kill();
-        assert!(result.is_ok(), "Failed to kill the process");
+    /// Reads and returns the stdout of the process, replacing invalid UTF-8
+    /// sequences with `U+FFFD REPLACEMENT CHARACTER`
+    pub fn stdout_lossy(&mut self) -> Result<String> {
+        let output = self.stdout_bytes()?;
+        Ok(String::from_utf8_lossy(&output).into_owned())
     }
 
-    #[test]
-    fn spawn_process_with_args_different_types() {
-        let process = Process::spawn_with_args("sleep", [String::from("1")])
-            .expect("Failed to spawn process");
-        assert!(process.pid() > 0);
-        thread::sleep(Duration::from_millis(100));
-        let result = process.kill();
-        assert!(result.is_ok(), "Failed to kill the process");
+    /// Reads and returns the stderr of the process, replacing invalid UTF-8
+    /// sequences with `U+FFFD REPLACEMENT CHARACTER`
+    pub fn stderr_lossy(&mut self) -> Result<String> {
+        let output = self.stderr_bytes()?;
+        Ok(String::from_utf8_lossy(&output).into_owned())
     }
 
-    #[test]
-    fn capture_stdout() {
-        let mut process =
-            Process::spawn_with_args("echo", ["hello world"]).expect("Failed to spawn process");
-        thread::sleep(Duration::from_millis(100));
-        let stdout = process.stdout().expect("Failed to read stdout");
-        assert_eq!(stdout.trim(), "hello world");
-        process.kill().expect("Failed to kill process");
+    /// Takes ownership of the stdout pipe of the process
+    ///
+    /// Returns [`None`] if stdout is not piped or was already taken.
+    pub fn take_stdout(&mut self) -> Option<ChildStdout> {
+        self.child.as_mut().and_then(|child| child.stdout.take())
     }
 
-    #[test]
-    fn capture_stderr() {
-        let mut process = Process::spawn_with_args("sh", ["-c", "echo error message >&2"])
-            .expect("Failed to spawn process");
-        thread::sleep(Duration::from_millis(100));
-        let stderr = process.stderr().expect("Failed to read stderr");
-        assert_eq!(stderr.trim(), "error message");
-        process.kill().expect("Failed to kill process");
+    /// Takes ownership of the stderr pipe of the process
+    ///
+    /// Returns [`None`] if stderr is not piped or was already taken.
+    pub fn take_stderr(&mut self) -> Option<ChildStderr> {
+        self.child.as_mut().and_then(|child| child.stderr.take())
     }
 
-    #[test]
-    fn capture_both_stdout_and_stderr() {
-        let mut process =
-            Process::spawn_with_args("sh", ["-c", "echo stdout message; echo stderr message >&2"])
-                .expect("Failed to spawn process");
-        thread::sleep(Duration::from_millis(100));
-        let stdout = process.stdout().expect("Failed to read stdout");
-        let stderr = process.stderr().expect("Failed to read stderr");
-        assert_eq!(stdout.trim(), "stdout message");
-        assert_eq!(stderr.trim(), "stderr message");
-        process.kill().expect("Failed to kill process");
+    /// Hands back a stdout pipe taken with [`Process::take_stdout`]
+    pub(crate) fn set_stdout(&mut self, stdout: ChildStdout) {
+        if let Some(ref mut child) = self.child {
+            child.stdout = Some(stdout);
+        }
     }
 
-    #[test]
-    fn capture_empty_stdout() {
-        let mut process = Process::spawn_with_args("true", Vec::<String>::new())
-            .expect("Failed to spawn process");
-        thread::sleep(Duration::from_millis(100));
-        let stdout = process.stdout().expect("Failed to read stdout");
-        assert_eq!(stdout, "");
-        process.kill().ok(); // Process might already be finished
+    /// Hands back a stderr pipe taken with [`Process::take_stderr`]
+    pub(crate) fn set_stderr(&mut self, stderr: ChildStderr) {
+        if let Some(ref mut child) = self.child {
+            child.stderr = Some(stderr);
+        }
+    }
+
+    /// Returns an iterator over the lines written by the process to stdout
+    ///
+    /// Lines are yielded as soon as the process writes them, which makes this
+    /// suitable to follow the output of long-running processes. The iterator
+    /// ends once the process closes its stdout stream.
+    ///
+    /// **Important:** The iterator takes ownership of the stdout handle. Subsequent
+    /// calls return an error instead of an empty iterator.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut process = Process::spawn_with_args("sh", ["-c", "echo one; sleep 1; echo two"])
+    ///     .expect("Failed to spawn");
+    ///
+    /// for line in process.stdout_lines().expect("Stdout is not available") {
+    ///     println!("{}", line.expect("Failed to read line"));
+    /// }
+    /// ```
+    pub fn stdout_lines(&mut self) -> Result<Lines<BufReader<ChildStdout>>> {
+        let Some(stdout) = self.take_stdout() else {
+            return Err(Error::StreamUnavailable {
+                pid: self.pid,
+                stream: "stdout",
+            });
+        };
+
+        Ok(BufReader::new(stdout).lines())
+    }
+
+    /// Returns an iterator over the lines written by the process to stderr
+    ///
+    /// See [`Process::stdout_lines`] for details.
+    pub fn stderr_lines(&mut self) -> Result<Lines<BufReader<ChildStderr>>> {
+        let Some(stderr) = self.take_stderr() else {
+            return Err(Error::StreamUnavailable {
+                pid: self.pid,
+                stream: "stderr",
+            });
+        };
+
+        Ok(BufReader::new(stderr).lines())
+    }
+
+    /// Collects runtime statistics of the process from `/proc`
+    ///
+    /// Reads `/proc/<pid>/stat`, `status`, `io` and `fd/`. The I/O counters
+    /// and open file descriptors of processes owned by other users are
+    /// usually not accessible and reported as [`None`].
+    ///
+    /// Fails with [`Error::NoSuchProcess`] once the process was reaped.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let process = Process::spawn_with_args("sleep", ["10"]).expect("Failed to spawn");
+    /// let stats = process.stats().expect("Failed to read stats");
+    /// println!("{:?} using {} bytes", stats.state, stats.rss_bytes);
+    /// ```
+    #[cfg(target_os = "linux")]
+    pub fn stats(&self) -> Result<ProcessStats> {
+        stats::read(self.running_pid()? as u32)
+    }
+
+    /// Sets the niceness of the running process, from `-20` (highest
+    /// priority) to `19` (lowest priority)
+    ///
+    /// Lowering the niceness requires `CAP_SYS_NICE`, [`Error::NotPermitted`]
+    /// is returned otherwise.
+    ///
+    /// **Note:** On Linux scheduling attributes belong to threads, changing
+    /// them on a running process only affects its main thread. Prefer the
+    /// options of [`ProcessBuilder`], which are inherited by every thread.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let process = Process::spawn("./batch-job").expect("Failed to spawn");
+    /// process.set_nice(19).expect("Failed to lower priority");
+    /// ```
+    pub fn set_nice(&self, nice: i32) -> Result<()> {
+        let pid = self.running_pid()?;
+        sched::set_nice(pid, nice).map_err(|err| Error::from_io(self.pid, "setpriority", err))
+    }
+
+    /// Restricts the running process to the CPUs at the given indices
+    ///
+    /// See the note on [`Process::set_nice`] about threads.
+    #[cfg(target_os = "linux")]
+    pub fn set_cpu_affinity<I: IntoIterator<Item = usize>>(&self, cpus: I) -> Result<()> {
+        let pid = self.running_pid()?;
+        let cpus = cpus.into_iter().collect::<Vec<_>>();
+        sched::set_affinity(pid, &cpus)
+            .map_err(|err| Error::from_io(self.pid, "sched_setaffinity", err))
+    }
+
+    /// Sets the scheduling policy of the running process
+    ///
+    /// See the note on [`Process::set_nice`] about threads.
+    #[cfg(target_os = "linux")]
+    pub fn set_sched_policy(&self, policy: SchedPolicy) -> Result<()> {
+        let pid = self.running_pid()?;
+        sched::set_policy(pid, policy)
+            .map_err(|err| Error::from_io(self.pid, "sched_setscheduler", err))
+    }
+
+    /// Sets the I/O scheduling class and priority of the running process
+    ///
+    /// See the note on [`Process::set_nice`] about threads.
+    #[cfg(target_os = "linux")]
+    pub fn set_io_priority(&self, priority: IoPriority) -> Result<()> {
+        let pid = self.running_pid()?;
+        sched::set_io_priority(pid, priority)
+            .map_err(|err| Error::from_io(self.pid, "ioprio_set", err))
+    }
+
+    /// Adjusts the likelihood of the running process being picked by the
+    /// OOM killer, from `-1000` (never) to `1000` (first)
+    #[cfg(target_os = "linux")]
+    pub fn set_oom_score_adj(&self, score: i32) -> Result<()> {
+        let pid = self.running_pid()?;
+        sched::set_oom_score_adj(pid as u32, score).map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => Error::NoSuchProcess { pid: self.pid },
+            _ => Error::from_io(self.pid, "oom_score_adj", err),
+        })
+    }
+
+    /// Returns the PID of the process, unless it was already reaped
+    fn running_pid(&self) -> Result<libc::pid_t> {
+        if self.status.is_some() {
+            // The PID could have been recycled after the process was reaped
+            return Err(Error::NoSuchProcess { pid: self.pid });
+        }
+
+        Ok(self.pid as libc::pid_t)
+    }
+
+    /// Waits for the process to exit and returns its [`ExitStatus`]
+    ///
+    /// The stdin handle of the process is closed before waiting to prevent
+    /// the process from blocking on input. Once collected, the status is
+    /// cached and returned by subsequent calls.
+    ///
+    /// Returns [`ExitStatus::Unavailable`] for processes attached with
+    /// [`Process::from_pid`].
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut process = Process::spawn_with_args("sh", ["-c", "exit 3"]).expect("Failed to spawn");
+    /// let status = process.wait().expect("Failed to wait for process");
+    /// assert_eq!(status.code(), Some(3));
+    /// ```
+    pub fn wait(&mut self) -> Result<ExitStatus> {
+        if let Some(status) = self.status {
+            return Ok(status);
+        }
+
+        let Some(ref mut child) = self.child else {
+            return Ok(self.wait_foreign(None)?.unwrap_or(ExitStatus::Unavailable));
+        };
+
+        // Close stdin first, the process might be waiting for input
+        drop(child.stdin.take());
+
+        Ok(self.reap(0)?.unwrap_or(ExitStatus::Unavailable))
+    }
+
+    /// Checks whether the process has exited without blocking
+    ///
+    /// Returns [`None`] if the process is still running.
+    pub fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
+        if let Some(status) = self.status {
+            return Ok(Some(status));
+        }
+
+        if self.child.is_none() {
+            return self.wait_foreign(Some(Duration::ZERO));
+        }
+
+        self.reap(libc::WNOHANG)
+    }
+
+    /// Waits for the process to exit without blocking the calling thread
+    ///
+    /// Behaves like [`Process::wait`], but returns a future which can be
+    /// awaited from any async runtime. On Linux 5.3 and later the future is
+    /// woken up by the pidfd of the process becoming readable, other
+    /// platforms check the process periodically.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut process = Process::spawn_with_args("sleep", ["1"]).expect("Failed to spawn");
+    /// let status = smol::block_on(process.wait_async()).expect("Failed to wait for process");
+    /// assert!(status.success());
+    /// ```
+    pub async fn wait_async(&mut self) -> Result<ExitStatus> {
+        self.close_stdin();
+        let mut delay = Duration::from_millis(1);
+
+        loop {
+            if let Some(status) = self.try_wait()? {
+                return Ok(status);
+            }
+
+            #[cfg(target_os = "linux")]
+            if let Some(ref pidfd) = self.pidfd {
+                reactor::readable(pidfd.as_fd())
+                    .await
+                    .map_err(|err| Error::from_io(self.pid, "wait for", err))?;
+                continue;
+            }
+
+            // Ignoring errors, a timer can not fail
+            let _ = reactor::sleep(delay).await;
+            delay = (delay * 2).min(Duration::from_millis(50));
+        }
+    }
+
+    /// Reaps the child process with `wait4(2)`, collecting its exit status
+    /// along with its [`ResourceUsage`]
+    ///
+    /// Returns [`None`] if `WNOHANG` is part of `options` and the process is
+    /// still running.
+    fn reap(&mut self, options: libc::c_int) -> Result<Option<ExitStatus>> {
+        let mut status = 0;
+        let mut usage = unsafe { std::mem::zeroed::<libc::rusage>() };
+
+        loop {
+            let result =
+                unsafe { libc::wait4(self.pid as libc::pid_t, &mut status, options, &mut usage) };
+
+            match result {
+                0 => return Ok(None),
+                -1 => {
+                    let err = io::Error::last_os_error();
+
+                    if err.kind() != io::ErrorKind::Interrupted {
+                        return Err(Error::from_io(self.pid, "wait for", err));
+                    }
+                }
+                _ => break,
+            }
+        }
+
+        let status = ExitStatus::from_raw(status);
+        self.status = Some(status);
+        self.usage = Some(ResourceUsage::from(usage));
+        self.join_stdin_feeder()?;
+        Ok(Some(status))
+    }
+
+    /// Waits for the process to exit and returns its [`ExitStatus`] along
+    /// with the resources it consumed
+    ///
+    /// Fails with [`Error::NotAChild`] for processes attached with
+    /// [`Process::from_pid`], the kernel only reports resource usage to the
+    /// parent of a process.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut process = Process::spawn_with_args("make", ["-j8"]).expect("Failed to spawn");
+    /// let (status, usage) = process.wait_with_usage().expect("Failed to wait for process");
+    /// println!("{status}: {:?} CPU, {} bytes peak RSS", usage.user_time + usage.system_time, usage.max_rss_bytes);
+    /// ```
+    pub fn wait_with_usage(&mut self) -> Result<(ExitStatus, ResourceUsage)> {
+        if self.child.is_none() {
+            return Err(Error::NotAChild { pid: self.pid });
+        }
+
+        let status = self.wait()?;
+        let usage = self.usage.unwrap_or_default();
+        Ok((status, usage))
+    }
+
+    /// Retrieves the resources consumed by the process once it has been
+    /// reaped
+    ///
+    /// Returns [`None`] while the process is running and for processes
+    /// attached with [`Process::from_pid`].
+    pub fn resource_usage(&self) -> Option<ResourceUsage> {
+        self.usage
+    }
+
+    /// Waits up to `timeout` for the process to exit
+    ///
+    /// Returns [`None`] if the process is still running once `timeout`
+    /// elapsed.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut process = Process::spawn_with_args("sleep", ["10"]).expect("Failed to spawn");
+    /// let status = process.wait_timeout(Duration::from_millis(100)).expect("Failed to wait");
+    /// assert!(status.is_none());
+    /// ```
+    pub fn wait_timeout(&mut self, timeout: Duration) -> Result<Option<ExitStatus>> {
+        if self.child.is_none() {
+            return self.wait_foreign(Some(timeout));
+        }
+
+        #[cfg(target_os = "linux")]
+        if self.status.is_none()
+            && let Some(ref pidfd) = self.pidfd
+        {
+            if !pidfd.wait(Some(timeout))? {
+                return Ok(None);
+            }
+
+            return self.try_wait();
+        }
+
+        let deadline = Instant::now() + timeout;
+        let mut delay = Duration::from_millis(1);
+
+        loop {
+            if let Some(status) = self.try_wait()? {
+                return Ok(Some(status));
+            }
+
+            let now = Instant::now();
+
+            if now >= deadline {
+                return Ok(None);
+            }
+
+            thread::sleep(delay.min(deadline - now));
+            delay = (delay * 2).min(Duration::from_millis(50));
+        }
+    }
+
+    /// Returns `true` if the process is still running
+    ///
+    /// Children which exited are reaped by this call.
+    pub fn is_alive(&mut self) -> Result<bool> {
+        Ok(self.try_wait()?.is_none())
+    }
+
+    /// Waits up to `timeout`, or forever when [`None`], for a process which
+    /// is not a child of the current process to exit
+    fn wait_foreign(&mut self, timeout: Option<Duration>) -> Result<Option<ExitStatus>> {
+        if let Some(status) = self.status {
+            return Ok(Some(status));
+        }
+
+        #[cfg(target_os = "linux")]
+        if let Some(ref pidfd) = self.pidfd {
+            if !pidfd.wait(timeout)? {
+                return Ok(None);
+            }
+
+            self.status = Some(ExitStatus::Unavailable);
+            return Ok(self.status);
+        }
+
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut delay = Duration::from_millis(1);
+
+        loop {
+            if !Self::exists(self.pid)? {
+                self.status = Some(ExitStatus::Unavailable);
+                return Ok(self.status);
+            }
+
+            let now = Instant::now();
+            let remaining = match deadline {
+                Some(deadline) if now >= deadline => return Ok(None),
+                Some(deadline) => deadline - now,
+                None => Duration::MAX,
+            };
+
+            thread::sleep(delay.min(remaining));
+            delay = (delay * 2).min(Duration::from_millis(50));
+        }
+    }
+
+    /// Waits for the process to exit while collecting everything it writes
+    /// to stdout and stderr
+    ///
+    /// Both pipes are drained concurrently, so a process producing large
+    /// amounts of output on either stream can not deadlock. This is the
+    /// recommended way to run a process to completion, see also
+    /// [`ProcessBuilder::output`].
+    ///
+    /// Streams which are not piped, or were already consumed, are reported
+    /// as empty. The output of a process spawned with
+    /// [`ProcessBuilder::pty`] is read from its terminal and reported as
+    /// stdout.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut process = Process::spawn_with_args("sh", ["-c", "echo out; echo err >&2"])
+    ///     .expect("Failed to spawn");
+    /// let output = process.wait_with_output().expect("Failed to wait for process");
+    /// assert!(output.status.success());
+    /// assert_eq!(output.stdout, b"out\n");
+    /// assert_eq!(output.stderr, b"err\n");
+    /// ```
+    pub fn wait_with_output(&mut self) -> Result<Output> {
+        let (mut stdout, stderr) = match self.child {
+            Some(ref mut child) => {
+                // Close stdin first, the process might be waiting for input
+                drop(child.stdin.take());
+                output::read2(child.stdout.take(), child.stderr.take())?
+            }
+            None => (Vec::new(), Vec::new()),
+        };
+
+        if let Some(mut pty) = self.pty.take() {
+            pty.read_to_end(&mut stdout)?;
+        }
+
+        Ok(Output {
+            status: self.wait()?,
+            usage: self.usage,
+            stdout,
+            stderr,
+        })
+    }
+
+    /// Delivers `signal` to the process referenced by this instance of
+    /// [`Process`]
+    ///
+    /// Fails with [`Error::NoSuchProcess`] if the process no longer exists
+    /// and with [`Error::NotPermitted`] if the caller is not allowed to
+    /// signal it.
+    ///
+    /// On Linux 5.3 and later the signal is delivered through a pidfd, so it
+    /// can never reach an unrelated process which reused the PID after the
+    /// process exited. Other platforms rely on `kill(2)`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use xprocess::{Process, Signal};
+    ///
+    /// let process = Process::spawn_with_args("sleep", ["10"]).expect("Failed to spawn");
+    /// process.signal(Signal::Int).expect("Failed to interrupt process");
+    /// ```
+    pub fn signal(&self, signal: Signal) -> Result<()> {
+        let pid = self.running_pid()?;
+
+        #[cfg(target_os = "linux")]
+        if let Some(ref pidfd) = self.pidfd {
+            return pidfd
+                .send_signal(signal)
+                .map_err(|err| Error::from_io(self.pid, "signal", err));
+        }
+
+        signal::send(pid, signal)
+    }
+
+    /// Asks the process to terminate by sending [`Signal::Term`]
+    pub fn terminate(&self) -> Result<()> {
+        self.signal(Signal::Term)
+    }
+
+    /// Forcefully kills the process by sending [`Signal::Kill`]
+    pub fn force_kill(&self) -> Result<()> {
+        self.signal(Signal::Kill)
+    }
+
+    /// Kills the process referenced by this instance of [`Process`]
+    ///
+    /// This is equivalent to [`Process::terminate`].
+    pub fn kill(&self) -> Result<()> {
+        self.terminate()
+    }
+
+    /// Delivers `signal` to every process in the process group created for
+    /// this process
+    ///
+    /// Unlike [`Process::signal`] this also reaches descendants of the
+    /// process, such as the commands of a `sh -c` pipeline, as long as they
+    /// did not move to a different process group. The group is still
+    /// signalled after the process itself exited, as long as other members
+    /// keep it alive. Once the process was reaped and the group is empty,
+    /// [`Error::NoSuchProcess`] is returned, since its ID could then be
+    /// recycled for an unrelated process.
+    ///
+    /// **Note:** The last member can still exit between this check and the
+    /// signal. Recycling the ID requires the kernel to allocate every other
+    /// free PID first, so hitting this race is very unlikely.
+    ///
+    /// Fails with [`Error::NoProcessGroup`] for processes spawned with
+    /// [`Detach::None`], which share the process group of the caller.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use xprocess::{Process, Signal};
+    ///
+    /// let process = Process::spawn_with_args("sh", ["-c", "sleep 10 | cat"]).expect("Failed to spawn");
+    /// process.signal_group(Signal::Kill).expect("Failed to kill process group");
+    /// ```
+    pub fn signal_group(&self, signal: Signal) -> Result<()> {
+        let Some(pgid) = self.pgid else {
+            return Err(Error::NoProcessGroup { pid: self.pid });
+        };
+
+        if self.status.is_some() && !self.group_survives(pgid as libc::pid_t) {
+            return Err(Error::NoSuchProcess { pid: self.pid });
+        }
+
+        signal::send(-(pgid as libc::pid_t), signal)
+    }
+
+    /// Whether the process group `pgid` still has members after the process
+    /// was reaped
+    ///
+    /// Members keep the ID of their group in use, so the kernel does not
+    /// recycle it as a PID while they exist. A process running with that PID
+    /// therefore means the group is gone, unless it is the leader of a group
+    /// this process joined.
+    fn group_survives(&self, pgid: libc::pid_t) -> bool {
+        match unsafe { libc::getpgid(pgid) } {
+            -1 if io::Error::last_os_error().raw_os_error() == Some(libc::ESRCH) => {
+                let alive = unsafe { libc::kill(-pgid, 0) } == 0;
+                alive || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+            }
+            leader_pgid => pgid as u32 != self.pid && leader_pgid == pgid,
+        }
+    }
+
+    /// Kills the process group created for this process
+    ///
+    /// This is the process group counterpart of [`Process::kill`] and sends
+    /// [`Signal::Term`], use [`Process::signal_group`] with [`Signal::Kill`]
+    /// for processes that ignore it.
+    pub fn kill_group(&self) -> Result<()> {
+        self.signal_group(Signal::Term)
+    }
+
+    /// Shuts the process down following `policy` and returns its final
+    /// [`ExitStatus`]
+    ///
+    /// Every step of the policy delivers its signal and waits for the process
+    /// to exit. When the process outlives every step it is killed with
+    /// [`Signal::Kill`], which cannot be ignored, so this method always
+    /// returns once the process is gone.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use std::time::Duration;
+    ///
+    /// use xprocess::{Process, Signal, StopPolicy};
+    ///
+    /// let mut process = Process::spawn_with_args("sleep", ["10"]).expect("Failed to spawn");
+    /// let policy = StopPolicy::new().then(Signal::Term, Duration::from_secs(5));
+    /// let status = process.stop(policy).expect("Failed to stop process");
+    /// assert_eq!(status.signal_name(), Some("SIGTERM"));
+    /// ```
+    pub fn stop(&mut self, policy: StopPolicy) -> Result<ExitStatus> {
+        if let Some(status) = self.try_wait()? {
+            return Ok(status);
+        }
+
+        for step in policy.steps() {
+            self.deliver(step.signal, policy.is_group())?;
+
+            if let Some(status) = self.wait_timeout(step.timeout)? {
+                return Ok(status);
+            }
+        }
+
+        self.deliver(Signal::Kill, policy.is_group())?;
+        self.wait()
+    }
+
+    /// Delivers `signal` to the process or its group, ignoring processes
+    /// which are already gone
+    fn deliver(&self, signal: Signal, group: bool) -> Result<()> {
+        let result = if group && self.pgid.is_some() {
+            self.signal_group(signal)
+        } else {
+            self.signal(signal)
+        };
+
+        match result {
+            Err(Error::NoSuchProcess { .. }) => Ok(()),
+            result => result,
+        }
+    }
+}
+
+impl Drop for Process {
+    /// Reaps the process if it already exited, so it does not linger as a
+    /// zombie, or kills its process group and waits for it when
+    /// [`ProcessBuilder::kill_on_drop`] is enabled
+    fn drop(&mut self) {
+        if self.child.is_none() || self.status.is_some() {
+            return;
+        }
+
+        if self.kill_on_drop {
+            self.deliver(Signal::Kill, true).ok();
+            self.wait().ok();
+        } else {
+            self.try_wait().ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::process::ExitStatusExt;
+    use std::sync::mpsc;
+
+    use super::*;
+
+    #[test]
+    fn spawn_process() {
+        let process = Process::spawn("sleep").expect("Failed to spawn process");
+        assert!(process.pid() > 0);
+        thread::sleep(Duration::from_millis(100));
+        let result = process.kill();
+        assert!(result.is_ok(), "Failed to kill the process");
+    }
+
+    #[test]
+    fn spawn_process_with_args() {
+        let process = Process::spawn_with_args("sleep", ["1"]).expect("Failed to spawn process");
+        assert!(process.pid() > 0);
+        thread::sleep(Duration::from_millis(100));
+        let result = process.kill();
+        assert!(result.is_ok(), "Failed to kill the process");
+    }
+
+    #[test]
+    fn spawn_process_with_args_different_types() {
+        let process = Process::spawn_with_args("sleep", [String::from("1")])
+            .expect("Failed to spawn process");
+        assert!(process.pid() > 0);
+        thread::sleep(Duration::from_millis(100));
+        let result = process.kill();
+        assert!(result.is_ok(), "Failed to kill the process");
+    }
+
+    #[test]
+    fn capture_stdout() {
+        let mut process =
+            Process::spawn_with_args("echo", ["hello world"]).expect("Failed to spawn process");
+        thread::sleep(Duration::from_millis(100));
+        let stdout = process.stdout().expect("Failed to read stdout");
+        assert_eq!(stdout.trim(), "hello world");
+        process.kill().expect("Failed to kill process");
+    }
+
+    #[test]
+    fn capture_stderr() {
+        let mut process = Process::spawn_with_args("sh", ["-c", "echo error message >&2"])
+            .expect("Failed to spawn process");
+        thread::sleep(Duration::from_millis(100));
+        let stderr = process.stderr().expect("Failed to read stderr");
+        assert_eq!(stderr.trim(), "error message");
+        process.kill().expect("Failed to kill process");
+    }
+
+    #[test]
+    fn capture_both_stdout_and_stderr() {
+        let mut process =
+            Process::spawn_with_args("sh", ["-c", "echo stdout message; echo stderr message >&2"])
+                .expect("Failed to spawn process");
+        thread::sleep(Duration::from_millis(100));
+        let stdout = process.stdout().expect("Failed to read stdout");
+        let stderr = process.stderr().expect("Failed to read stderr");
+        assert_eq!(stdout.trim(), "stdout message");
+        assert_eq!(stderr.trim(), "stderr message");
+        process.kill().expect("Failed to kill process");
+    }
+
+    #[test]
+    fn capture_empty_stdout() {
+        let mut process = Process::spawn_with_args("true", Vec::<String>::new())
+            .expect("Failed to spawn process");
+        thread::sleep(Duration::from_millis(100));
+        let stdout = process.stdout().expect("Failed to read stdout");
+        assert_eq!(stdout, "");
+        process.kill().ok(); // Process might already be finished
+    }
+
+    #[test]
+    fn signal_process() {
+        let mut process = Process::spawn_with_args(
+            "sh",
+            [
+                "-c",
+                "trap 'echo interrupted; exit 0' INT; while true; do sleep 0.05; done",
+            ],
+        )
+        .expect("Failed to spawn process");
+        thread::sleep(Duration::from_millis(100));
+        process
+            .signal(Signal::Int)
+            .expect("Failed to signal process");
+        let stdout = process.stdout().expect("Failed to read stdout");
+        assert_eq!(stdout.trim(), "interrupted");
+    }
+
+    #[test]
+    fn force_kill_process() {
+        let mut process = Process::spawn_with_args("sh", ["-c", "trap '' TERM; exec sleep 5"])
+            .expect("Failed to spawn process");
+        thread::sleep(Duration::from_millis(100));
+        process.terminate().expect("Failed to terminate process");
+        process.force_kill().expect("Failed to kill process");
+        let status = process.wait().expect("Failed to wait for process");
+        assert_eq!(status.signal_name(), Some("SIGKILL"));
+    }
+
+    #[test]
+    fn signal_exited_process() {
+        let mut process = Process::spawn("true").expect("Failed to spawn process");
+        process.wait().expect("Failed to wait for process");
+        let err = process.terminate().expect_err("Process should be gone");
+        assert!(matches!(err, Error::NoSuchProcess { .. }));
+    }
+
+    #[test]
+    fn process_leads_group_and_session() {
+        let mut process =
+            Process::spawn_with_args("sleep", ["1"]).expect("Failed to spawn process");
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(process.pgid().expect("Failed to get pgid"), process.pid());
+        assert_eq!(process.sid().expect("Failed to get sid"), process.pid());
+        process.force_kill().expect("Failed to kill process");
+        process.wait().expect("Failed to wait for process");
+        assert!(matches!(process.pgid(), Err(Error::NoSuchProcess { .. })));
+        assert!(matches!(process.sid(), Err(Error::NoSuchProcess { .. })));
+    }
+
+    #[test]
+    fn kill_group_reaches_descendants() {
+        let mut process = Process::spawn_with_args("sh", ["-c", "sleep 30 & sleep 30; wait"])
+            .expect("Failed to spawn process");
+        thread::sleep(Duration::from_millis(100));
+        let started = Instant::now();
+        process.kill_group().expect("Failed to kill process group");
+        // Stdout reaches EOF once every process holding the pipe is gone
+        process.stdout().expect("Failed to read stdout");
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn signal_group_after_reaping() {
+        let mut process = Process::builder("sh")
+            .args(["-c", "sleep 30 & exit 0"])
+            .stdout(StdioMode::Null)
+            .spawn()
+            .expect("Failed to spawn process");
+        assert!(
+            process
+                .wait()
+                .expect("Failed to wait for process")
+                .success()
+        );
+
+        // The background `sleep` keeps the group alive
+        process
+            .signal_group(Signal::Kill)
+            .expect("Failed to kill process group");
+
+        let started = Instant::now();
+        while !matches!(
+            process.signal_group(Signal::Kill),
+            Err(Error::NoSuchProcess { .. })
+        ) {
+            assert!(started.elapsed() < Duration::from_secs(5));
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn wait_for_exit_code() {
+        let mut process =
+            Process::spawn_with_args("sh", ["-c", "exit 3"]).expect("Failed to spawn process");
+        let status = process.wait().expect("Failed to wait for process");
+        assert_eq!(status, ExitStatus::Exited(3));
+        assert_eq!(process.wait().expect("Failed to wait for process"), status);
+        assert_eq!(
+            process.try_wait().expect("Failed to wait for process"),
+            Some(status)
+        );
+    }
+
+    #[test]
+    fn wait_for_signal() {
+        let mut process =
+            Process::spawn_with_args("sleep", ["10"]).expect("Failed to spawn process");
+        assert_eq!(
+            process.try_wait().expect("Failed to wait for process"),
+            None
+        );
+        process.terminate().expect("Failed to terminate process");
+        let status = process.wait().expect("Failed to wait for process");
+        assert_eq!(status.signal(), Some(libc::SIGTERM));
+        assert_eq!(status.signal_name(), Some("SIGTERM"));
+        assert!(!status.success());
+    }
+
+    #[test]
+    fn wait_with_timeout() {
+        let mut process =
+            Process::spawn_with_args("sleep", ["10"]).expect("Failed to spawn process");
+        let status = process
+            .wait_timeout(Duration::from_millis(100))
+            .expect("Failed to wait for process");
+        assert_eq!(status, None);
+        process.force_kill().expect("Failed to kill process");
+        let status = process
+            .wait_timeout(Duration::from_secs(5))
+            .expect("Failed to wait for process");
+        assert_eq!(
+            status.and_then(|status| status.signal()),
+            Some(libc::SIGKILL)
+        );
+    }
+
+    #[test]
+    fn stop_with_first_signal() {
+        let mut process =
+            Process::spawn_with_args("sleep", ["10"]).expect("Failed to spawn process");
+        let policy = StopPolicy::new().then(Signal::Term, Duration::from_secs(5));
+        let status = process.stop(policy).expect("Failed to stop process");
+        assert_eq!(status.signal_name(), Some("SIGTERM"));
+    }
+
+    #[test]
+    fn stop_escalates_to_kill() {
+        let mut process = Process::spawn_with_args("sh", ["-c", "trap '' TERM INT; exec sleep 10"])
+            .expect("Failed to spawn process");
+        thread::sleep(Duration::from_millis(100));
+        let policy = StopPolicy::new()
+            .then(Signal::Int, Duration::from_millis(100))
+            .then(Signal::Term, Duration::from_millis(100));
+        let started = Instant::now();
+        let status = process.stop(policy).expect("Failed to stop process");
+        assert_eq!(status.signal_name(), Some("SIGKILL"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn stop_exited_process() {
+        let mut process = Process::spawn("true").expect("Failed to spawn process");
+        process.wait().expect("Failed to wait for process");
+        let status = process
+            .stop(StopPolicy::default())
+            .expect("Failed to stop process");
+        assert!(status.success());
+    }
+
+    #[test]
+    fn wait_with_output() {
+        let mut process = Process::spawn_with_args("sh", ["-c", "echo out; echo err >&2; exit 2"])
+            .expect("Failed to spawn process");
+        let output = process
+            .wait_with_output()
+            .expect("Failed to wait for process");
+        assert_eq!(output.status, ExitStatus::Exited(2));
+        assert_eq!(output.stdout, b"out\n");
+        assert_eq!(output.stderr, b"err\n");
+    }
+
+    #[test]
+    fn wait_with_output_does_not_deadlock() {
+        // Writes more than a pipe buffer to stderr before touching stdout
+        let mut process =
+            Process::spawn_with_args("sh", ["-c", "head -c 1000000 /dev/zero >&2; echo done"])
+                .expect("Failed to spawn process");
+        let output = process
+            .wait_with_output()
+            .expect("Failed to wait for process");
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"done\n");
+        assert_eq!(output.stderr.len(), 1_000_000);
+    }
+
+    #[test]
+    fn stream_stdout_lines() {
+        let mut process =
+            Process::spawn_with_args("sh", ["-c", "echo first; sleep 5; echo second"])
+                .expect("Failed to spawn process");
+        let lines = process.stdout_lines().expect("Failed to get stdout lines");
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            for line in lines {
+                tx.send(line.expect("Failed to read line")).ok();
+            }
+        });
+
+        // The first line is available while the process is still running
+        let first = rx
+            .recv_timeout(Duration::from_secs(2))
+            .expect("Line was not streamed");
+        assert_eq!(first, "first");
+        assert_eq!(
+            process.try_wait().expect("Failed to wait for process"),
+            None
+        );
+        process.kill_group().expect("Failed to kill process group");
+    }
+
+    #[test]
+    fn stream_stderr_lines() {
+        let mut process = Process::spawn_with_args("sh", ["-c", "echo a >&2; echo b >&2"])
+            .expect("Failed to spawn process");
+        let lines = process
+            .stderr_lines()
+            .expect("Failed to get stderr lines")
+            .collect::<io::Result<Vec<_>>>()
+            .expect("Failed to read lines");
+        assert_eq!(lines, ["a", "b"]);
+        assert!(process.stderr_lines().is_err());
+        assert!(process.take_stderr().is_none());
+    }
+
+    #[test]
+    fn take_stdout_handle() {
+        let mut process =
+            Process::spawn_with_args("echo", ["raw"]).expect("Failed to spawn process");
+        let mut stdout = process.take_stdout().expect("Stdout is not piped");
+        let mut output = String::new();
+        stdout
+            .read_to_string(&mut output)
+            .expect("Failed to read stdout");
+        assert_eq!(output, "raw\n");
+        assert!(process.take_stdout().is_none());
+    }
+
+    #[test]
+    fn capture_binary_output() {
+        let mut process = Process::spawn_with_args("printf", ["\\377\\000\\001"])
+            .expect("Failed to spawn process");
+        let stdout = process.stdout_bytes().expect("Failed to read stdout");
+        assert_eq!(stdout, [0xff, 0x00, 0x01]);
+    }
+
+    #[test]
+    fn capture_invalid_utf8() {
+        let script = "printf 'caf\\351'; printf 'caf\\351' >&2";
+        let mut process =
+            Process::spawn_with_args("sh", ["-c", script]).expect("Failed to spawn process");
+        assert_eq!(
+            process.stdout_lossy().expect("Failed to read stdout"),
+            "caf\u{fffd}"
+        );
+        assert_eq!(
+            process.stderr_bytes().expect("Failed to read stderr"),
+            b"caf\xe9"
+        );
+
+        let mut process =
+            Process::spawn_with_args("sh", ["-c", script]).expect("Failed to spawn process");
+        assert!(process.stdout().is_err());
+        assert_eq!(
+            process.stderr_lossy().expect("Failed to read stderr"),
+            "caf\u{fffd}"
+        );
+    }
+
+    /// Returns `true` if `pid` is a child of this process which was not
+    /// reaped yet
+    fn is_unreaped_child(pid: u32) -> bool {
+        let mut status = 0;
+        unsafe { libc::waitpid(pid as libc::pid_t, &mut status, libc::WNOHANG) == 0 }
+    }
+
+    #[test]
+    fn drop_reaps_exited_process() {
+        let process = Process::spawn("true").expect("Failed to spawn process");
+        let pid = process.pid();
+        thread::sleep(Duration::from_millis(100));
+        drop(process);
+        let mut status = 0;
+        let result = unsafe { libc::waitpid(pid as libc::pid_t, &mut status, libc::WNOHANG) };
+        assert_eq!(result, -1, "Process was not reaped");
+        assert_eq!(
+            io::Error::last_os_error().raw_os_error(),
+            Some(libc::ECHILD)
+        );
+    }
+
+    #[test]
+    fn kill_on_drop_kills_group() {
+        let mut process = ProcessBuilder::new("sh")
+            .args(["-c", "sleep 30 & sleep 30; wait"])
+            .kill_on_drop(true)
+            .spawn()
+            .expect("Failed to spawn process");
+        let mut stdout = process.take_stdout().expect("Stdout is not piped");
+        thread::sleep(Duration::from_millis(100));
+        let started = Instant::now();
+        drop(process);
+        // Stdout reaches EOF once every process holding the pipe is gone
+        stdout.read_to_end(&mut Vec::new()).unwrap();
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn detach_keeps_process_running() {
+        let process = ProcessBuilder::new("sleep")
+            .arg("10")
+            .kill_on_drop(true)
+            .spawn()
+            .expect("Failed to spawn process");
+        let pid = process.detach();
+        assert!(is_unreaped_child(pid));
+        unsafe {
+            libc::kill(pid as libc::pid_t, libc::SIGKILL);
+            libc::waitpid(pid as libc::pid_t, std::ptr::null_mut(), 0);
+        }
+    }
+
+    #[test]
+    fn spawn_missing_command() {
+        let err = Process::spawn("xprocess-missing-command").expect_err("Command should not exist");
+        assert!(
+            matches!(err, Error::NotFound { ref command, .. } if command == "xprocess-missing-command")
+        );
+    }
+
+    #[test]
+    fn read_invalid_utf8_stdout() {
+        let mut process =
+            Process::spawn_with_args("printf", ["\\377"]).expect("Failed to spawn process");
+        let err = process.stdout().expect_err("Output should not be UTF-8");
+        assert!(matches!(
+            err,
+            Error::NotUtf8 {
+                stream: "stdout",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn attach_to_running_process() {
+        let mut child = Command::new("sleep").arg("10").spawn().unwrap();
+        let mut process = Process::from_pid(child.id()).expect("Failed to attach to process");
+        assert!(process.is_alive().expect("Failed to check process"));
+        assert_eq!(
+            process
+                .wait_timeout(Duration::from_millis(50))
+                .expect("Failed to wait for process"),
+            None
+        );
+
+        process.terminate().expect("Failed to terminate process");
+        // Only the parent can reap the process
+        let status = child.wait().unwrap();
+        assert_eq!(status.signal(), Some(libc::SIGTERM));
+
+        let status = process.wait().expect("Failed to wait for process");
+        assert_eq!(status, ExitStatus::Unavailable);
+        assert!(!process.is_alive().expect("Failed to check process"));
+        assert!(matches!(
+            process.terminate(),
+            Err(Error::NoSuchProcess { .. })
+        ));
+    }
+
+    #[test]
+    fn attach_to_missing_process() {
+        let err = Process::from_pid(libc::pid_t::MAX as u32).expect_err("Process should not exist");
+        assert!(matches!(err, Error::NoSuchProcess { .. }));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn spawned_process_holds_pidfd() {
+        let mut process =
+            Process::spawn_with_args("sleep", ["10"]).expect("Failed to spawn process");
+
+        if process.pidfd.is_none() {
+            // Kernels older than 5.3 lack `pidfd_open`
+            return;
+        }
+
+        let started = Instant::now();
+        assert_eq!(
+            process
+                .wait_timeout(Duration::from_millis(100))
+                .expect("Failed to wait for process"),
+            None
+        );
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        process.force_kill().expect("Failed to kill process");
+        let status = process
+            .wait_timeout(Duration::from_secs(5))
+            .expect("Failed to wait for process");
+        assert_eq!(
+            status.and_then(|status| status.signal()),
+            Some(libc::SIGKILL)
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn collect_stats() {
+        let mut process =
+            Process::spawn_with_args("sleep", ["10"]).expect("Failed to spawn process");
+        thread::sleep(Duration::from_millis(100));
+        let stats = process.stats().expect("Failed to read stats");
+        assert_eq!(stats.state, ProcessState::Sleeping);
+        assert_eq!(stats.threads, 1);
+        assert!(stats.rss_bytes > 0);
+        assert!(stats.vsz_bytes >= stats.rss_bytes);
+        // stdin, stdout and stderr
+        assert!(stats.open_fds.is_some_and(|fds| fds >= 3));
+        let age = std::time::SystemTime::now()
+            .duration_since(stats.start_time)
+            .unwrap_or_default();
+        assert!(age < Duration::from_secs(60));
+
+        process.force_kill().expect("Failed to kill process");
+        process.wait().expect("Failed to wait for process");
+        assert!(matches!(process.stats(), Err(Error::NoSuchProcess { .. })));
+    }
+
+    #[test]
+    fn wait_with_resource_usage() {
+        let mut process = Process::spawn_with_args(
+            "sh",
+            ["-c", "i=0; while [ $i -lt 20000 ]; do i=$((i+1)); done"],
+        )
+        .expect("Failed to spawn process");
+        assert_eq!(process.resource_usage(), None);
+        let (status, usage) = process
+            .wait_with_usage()
+            .expect("Failed to wait for process");
+        assert!(status.success());
+        assert!(usage.user_time + usage.system_time > Duration::ZERO);
+        assert!(usage.max_rss_bytes > 0);
+        assert_eq!(process.resource_usage(), Some(usage));
+    }
+
+    #[test]
+    fn resource_usage_of_non_child() {
+        let mut child = Command::new("true").spawn().unwrap();
+        let mut process = Process::from_pid(child.id()).expect("Failed to attach to process");
+        child.wait().unwrap();
+        assert!(matches!(
+            process.wait_with_usage(),
+            Err(Error::NotAChild { .. })
+        ));
+    }
+
+    #[test]
+    fn adjust_running_process() {
+        let mut process =
+            Process::spawn_with_args("sleep", ["10"]).expect("Failed to spawn process");
+        let pid = process.pid() as libc::pid_t;
+
+        process.set_nice(12).expect("Failed to set niceness");
+        assert_eq!(
+            unsafe { libc::getpriority(libc::PRIO_PROCESS, pid as libc::id_t) },
+            12
+        );
+
+        #[cfg(target_os = "linux")]
+        {
+            process
+                .set_sched_policy(SchedPolicy::Idle)
+                .expect("Failed to set policy");
+            assert_eq!(unsafe { libc::sched_getscheduler(pid) }, libc::SCHED_IDLE);
+            process
+                .set_io_priority(IoPriority::Idle)
+                .expect("Failed to set I/O priority");
+            process
+                .set_oom_score_adj(1000)
+                .expect("Failed to set OOM score");
+            let score = std::fs::read_to_string(format!("/proc/{pid}/oom_score_adj"))
+                .expect("Failed to read oom_score_adj");
+            assert_eq!(score.trim(), "1000");
+            let err = process
+                .set_cpu_affinity([])
+                .expect_err("Empty CPU set should be rejected");
+            assert_eq!(err.errno(), Some(libc::EINVAL));
+        }
+
+        process.force_kill().expect("Failed to kill process");
+        process.wait().expect("Failed to wait for process");
+        assert!(matches!(
+            process.set_nice(0),
+            Err(Error::NoSuchProcess { .. })
+        ));
+    }
+
+    #[test]
+    fn interact_through_pty() {
+        let mut process = ProcessBuilder::new("sh")
+            .args(["-c", "read line; echo \"got $line\"; stty size"])
+            .pty(WindowSize::default())
+            .spawn()
+            .expect("Failed to spawn process");
+        assert!(process.take_stdout().is_none());
+
+        process
+            .set_window_size(WindowSize::new(10, 40))
+            .expect("Failed to resize terminal");
+        let pty = process.pty().expect("No terminal allocated");
+        pty.write_all(b"hello\n")
+            .expect("Failed to write to terminal");
+
+        let mut transcript = String::new();
+        pty.read_to_string(&mut transcript)
+            .expect("Failed to read terminal");
+        // Input is echoed back by the terminal
+        assert_eq!(transcript, "hello\r\ngot hello\r\n10 40\r\n");
+        assert!(
+            process
+                .wait()
+                .expect("Failed to wait for process")
+                .success()
+        );
+        assert!(process.take_pty().is_some());
+    }
+
+    #[test]
+    fn wait_for_exit_asynchronously() {
+        let mut process = Process::spawn_with_args("sh", ["-c", "sleep 0.1; exit 3"])
+            .expect("Failed to spawn process");
+        #[cfg(target_os = "linux")]
+        assert!(process.pidfd().is_some());
+
+        let status = reactor::block_on(process.wait_async()).expect("Failed to wait for process");
+        assert_eq!(status.code(), Some(3));
+        assert_eq!(process.try_wait().unwrap(), Some(status));
+
+        let mut foreign = Process::spawn_with_args("sleep", ["0.1"]).expect("Failed to spawn");
+        let mut attached = Process::from_pid(foreign.pid()).expect("Failed to attach");
+        let handle = thread::spawn(move || foreign.wait());
+        let status = reactor::block_on(attached.wait_async()).expect("Failed to wait for process");
+        assert_eq!(status, ExitStatus::Unavailable);
+        assert!(handle.join().unwrap().unwrap().success());
     }
 }
@@ -0,0 +1,69 @@
+use std::borrow::Cow;
+use std::io::{self, Read};
+use std::process::{ChildStderr, ChildStdout};
+use std::thread;
+
+use crate::{ExitStatus, ResourceUsage};
+
+/// Output collected from a [`Process`] which ran to completion
+///
+/// [`Process`]: crate::Process
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Output {
+    /// How the process ended
+    pub status: ExitStatus,
+    /// Resources consumed by the process, [`None`] unless it was a child of
+    /// the current process
+    pub usage: Option<ResourceUsage>,
+    /// Bytes written by the process to stdout
+    pub stdout: Vec<u8>,
+    /// Bytes written by the process to stderr
+    pub stderr: Vec<u8>,
+}
+
+impl Output {
+    /// Decodes stdout as UTF-8, replacing invalid sequences with
+    /// `U+FFFD REPLACEMENT CHARACTER`
+    pub fn stdout_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.stdout)
+    }
+
+    /// Decodes stderr as UTF-8, replacing invalid sequences with
+    /// `U+FFFD REPLACEMENT CHARACTER`
+    pub fn stderr_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.stderr)
+    }
+}
+
+/// Reads stdout and stderr to EOF concurrently
+///
+/// Stderr is drained on a separate thread so a process filling up one pipe
+/// while the other is being read can not deadlock.
+pub(crate) fn read2(
+    stdout: Option<ChildStdout>,
+    stderr: Option<ChildStderr>,
+) -> io::Result<(Vec<u8>, Vec<u8>)> {
+    let stderr_reader = stderr.map(|mut stderr| {
+        thread::spawn(move || -> io::Result<Vec<u8>> {
+            let mut buf = Vec::new();
+            stderr.read_to_end(&mut buf)?;
+            Ok(buf)
+        })
+    });
+
+    let mut stdout_buf = Vec::new();
+    let stdout_result = match stdout {
+        Some(mut stdout) => stdout.read_to_end(&mut stdout_buf).map(|_| ()),
+        None => Ok(()),
+    };
+
+    let stderr_buf = match stderr_reader {
+        Some(handle) => handle
+            .join()
+            .map_err(|_| io::Error::other("stderr reader thread panicked"))??,
+        None => Vec::new(),
+    };
+
+    stdout_result?;
+    Ok((stdout_buf, stderr_buf))
+}
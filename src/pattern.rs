@@ -0,0 +1,942 @@
+use std::collections::HashSet;
+
+use crate::{Error, Result};
+
+// Why this engine lives here rather than using the `regex` crate:
+//
+// * `libc` is the only dependency of the crate, and `regex` alone would
+//   outweigh all of it in build time and binary size.
+// * `Interaction::expect` searches output which keeps growing. It needs
+//   `search_from` to resume after the positions which can no longer match,
+//   and the `regex` API has no way to report that position.
+// * Only a small, fixed syntax subset is needed. The program size, the
+//   memory used by a search and its running time are all bounded.
+//
+// Keep the syntax limited to what is documented on `Pattern`. If the crate
+// ever takes on heavier dependencies, replace this module with
+// `regex-automata`, whose lower-level API can report where to resume.
+
+/// Maximum number of instructions of a compiled pattern, which bounds the
+/// memory used by counted repetitions such as `a{1000}`
+const MAX_PROGRAM_LEN: usize = 10_000;
+
+/// Number of `(instruction, position)` pairs above which the states already
+/// explored by a search are tracked in a hash set instead of a bitset,
+/// bounding the bitset to 8 MiB
+const MAX_DENSE_STATES: usize = 1 << 26;
+
+/// Result of parsing or compiling a pattern, failing with the reason
+/// reported in [`Error::InvalidPattern`]
+type SyntaxResult<T> = std::result::Result<T, String>;
+
+/// Regular expression matched against the output of a process by
+/// [`Interaction::expect`]
+///
+/// Supports the usual subset of regular expression syntax:
+///
+/// * literals, `.` (any character but a newline) and escapes such as `\.`,
+///   `\n`, `\r`, `\t` or `\x1b`
+/// * classes such as `[a-z_]` or `[^0-9]`, and `\d`, `\w`, `\s` along with
+///   their negations `\D`, `\W`, `\S`
+/// * anchors `^` and `$`, matching at the start and end of the unread
+///   output, and word boundaries `\b` and `\B`
+/// * capturing `(...)` and non-capturing `(?:...)` groups, alternations
+///   `a|b`
+/// * greedy quantifiers `*`, `+`, `?`, `{n}`, `{n,}` and `{n,m}`, made lazy
+///   with a trailing `?`
+///
+/// Matching uses a bounded backtracking search, which runs in time linear to
+/// the length of the output for a given pattern.
+///
+/// # Example
+///
+/// ```ignore
+/// use xprocess::Pattern;
+///
+/// let prompt = Pattern::new(r"Password for (\w+): $").expect("Invalid pattern");
+/// assert!(prompt.is_match("Password for admin: "));
+/// ```
+///
+/// [`Interaction::expect`]: crate::Interaction::expect
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    source: String,
+    program: Vec<Inst>,
+    /// Number of capture groups, including the implicit group of the whole
+    /// match
+    groups: usize,
+}
+
+impl Pattern {
+    /// Compiles `pattern`, failing with [`Error::InvalidPattern`] if its
+    /// syntax is not supported
+    pub fn new(pattern: &str) -> Result<Self> {
+        let invalid = |reason: String| Error::InvalidPattern {
+            pattern: pattern.to_owned(),
+            reason,
+        };
+        let mut parser = Parser {
+            chars: pattern.chars().collect(),
+            pos: 0,
+            groups: 1,
+        };
+        let node = parser.parse_alternation().map_err(invalid)?;
+
+        if let Some(c) = parser.peek() {
+            return Err(invalid(format!("unmatched '{c}'")));
+        }
+
+        let mut compiler = Compiler {
+            program: Vec::new(),
+        };
+        compiler.emit(Inst::Save(0)).map_err(invalid)?;
+        compiler.compile(&node).map_err(invalid)?;
+        compiler.emit(Inst::Save(1)).map_err(invalid)?;
+        compiler.emit(Inst::Match).map_err(invalid)?;
+
+        Ok(Self {
+            source: pattern.to_owned(),
+            program: compiler.program,
+            groups: parser.groups,
+        })
+    }
+
+    /// Builds a pattern matching `text` literally
+    pub fn literal(text: &str) -> Self {
+        let mut escaped = String::with_capacity(text.len());
+
+        for c in text.chars() {
+            if is_meta(c) {
+                escaped.push('\\');
+            }
+
+            escaped.push(c);
+        }
+
+        Self::new(&escaped).expect("Escaped literals are valid patterns")
+    }
+
+    /// Returns the source of the pattern
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Checks whether the pattern matches anywhere in `text`
+    pub fn is_match(&self, text: &str) -> bool {
+        self.search(text).is_some()
+    }
+
+    /// Finds the leftmost match in `text` and returns the byte range of each
+    /// capture group, the first one being the whole match
+    pub(crate) fn search(&self, text: &str) -> Option<Vec<Option<(usize, usize)>>> {
+        match self.search_from(text, 0, &mut Scratch::default()) {
+            Searched::Match(groups) => Some(groups),
+            Searched::NoMatch { .. } => None,
+        }
+    }
+
+    /// Finds the leftmost match in `text` starting at byte offset `from` or
+    /// later, reusing the buffers of `scratch`
+    ///
+    /// Without a match, reports the offset where the search has to resume
+    /// once more text was appended: matches starting earlier were ruled out
+    /// without looking at the end of `text`, so appending can not change
+    /// the outcome for them.
+    pub(crate) fn search_from(&self, text: &str, from: usize, scratch: &mut Scratch) -> Searched {
+        let text_from = &text[from..];
+        scratch.chars.clear();
+        scratch.chars.extend(text_from.chars());
+        scratch.offsets.clear();
+        scratch.offsets.extend(
+            text_from
+                .char_indices()
+                .map(|(offset, _)| from + offset)
+                .chain([text.len()]),
+        );
+
+        let states = self.program.len() * (scratch.chars.len() + 1);
+        let dense = states <= MAX_DENSE_STATES;
+
+        if dense {
+            scratch.visited.clear();
+            scratch.visited.resize(states.div_ceil(64), 0);
+        } else {
+            scratch.sparse.clear();
+        }
+
+        scratch.slots.clear();
+        scratch.slots.resize(self.groups * 2, None);
+
+        let mut search = Search {
+            program: &self.program,
+            chars: &scratch.chars,
+            before: text[..from].chars().next_back(),
+            at_start: from == 0,
+            dense,
+            visited: &mut scratch.visited,
+            sparse: &mut scratch.sparse,
+            slots: &mut scratch.slots,
+            stack: &mut scratch.stack,
+            reached_end: false,
+        };
+        let mut resume = None;
+
+        // Failing from an instruction at a position does not depend on where
+        // the match started, so `visited` is shared by every attempt
+        for start in 0..=search.chars.len() {
+            search.reached_end = false;
+
+            if search.run(start) {
+                let groups = search
+                    .slots
+                    .chunks(2)
+                    .map(|slots| match *slots {
+                        [Some(start), Some(end)] => {
+                            Some((scratch.offsets[start], scratch.offsets[end]))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                return Searched::Match(groups);
+            }
+
+            if search.reached_end && resume.is_none() {
+                resume = Some(start);
+            }
+        }
+
+        // The last attempt starts at the end, so it always reaches it
+        let resume = resume.unwrap_or(search.chars.len());
+        Searched::NoMatch {
+            resume: scratch.offsets[resume],
+        }
+    }
+}
+
+/// Outcome of [`Pattern::search_from`]
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Searched {
+    /// Byte range of each capture group, the first one being the whole match
+    Match(Vec<Option<(usize, usize)>>),
+    /// No match, the next search may start at byte offset `resume`
+    NoMatch { resume: usize },
+}
+
+/// Buffers reused by successive calls to [`Pattern::search_from`]
+#[derive(Debug, Default)]
+pub(crate) struct Scratch {
+    chars: Vec<char>,
+    /// Byte offset of each character, followed by the length of the text
+    offsets: Vec<usize>,
+    visited: Vec<u64>,
+    sparse: HashSet<usize>,
+    slots: Vec<Option<usize>>,
+    stack: Vec<Job>,
+}
+
+/// Checks whether `c` has a special meaning outside of classes
+fn is_meta(c: char) -> bool {
+    matches!(
+        c,
+        '\\' | '.' | '+' | '*' | '?' | '(' | ')' | '|' | '[' | ']' | '{' | '}' | '^' | '$'
+    )
+}
+
+fn is_word(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+const DIGIT: &[(char, char)] = &[('0', '9')];
+const WORD: &[(char, char)] = &[('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')];
+const SPACE: &[(char, char)] = &[('\t', '\r'), (' ', ' ')];
+
+/// Set of characters matched by a bracket expression or a shorthand class
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Class {
+    ranges: Vec<(char, char)>,
+    negated: bool,
+}
+
+impl Class {
+    fn matches(&self, c: char) -> bool {
+        let found = self
+            .ranges
+            .iter()
+            .any(|&(start, end)| start <= c && c <= end);
+        found != self.negated
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Assertion {
+    Start,
+    End,
+    WordBoundary,
+    NotWordBoundary,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Node {
+    Empty,
+    Literal(char),
+    Any,
+    Class(Class),
+    Assert(Assertion),
+    Group(Box<Node>, Option<usize>),
+    Concat(Vec<Node>),
+    Alternate(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+        greedy: bool,
+    },
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    /// Number of capture groups found so far
+    groups: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            return true;
+        }
+
+        false
+    }
+
+    fn parse_alternation(&mut self) -> SyntaxResult<Node> {
+        let mut alternatives = vec![self.parse_concat()?];
+
+        while self.eat('|') {
+            alternatives.push(self.parse_concat()?);
+        }
+
+        Ok(match alternatives.len() {
+            1 => alternatives.remove(0),
+            _ => Node::Alternate(alternatives),
+        })
+    }
+
+    fn parse_concat(&mut self) -> SyntaxResult<Node> {
+        let mut nodes = Vec::new();
+
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+
+            let atom = self.parse_atom()?;
+            nodes.push(self.parse_quantifiers(atom)?);
+        }
+
+        Ok(match nodes.len() {
+            0 => Node::Empty,
+            1 => nodes.remove(0),
+            _ => Node::Concat(nodes),
+        })
+    }
+
+    fn parse_quantifiers(&mut self, mut node: Node) -> SyntaxResult<Node> {
+        loop {
+            let start = self.pos;
+            let (min, max) = match self.peek() {
+                Some('*') => (0, None),
+                Some('+') => (1, None),
+                Some('?') => (0, Some(1)),
+                Some('{') => match self.parse_counts()? {
+                    Some(counts) => counts,
+                    None => return Ok(node),
+                },
+                _ => return Ok(node),
+            };
+
+            if self.pos == start {
+                self.pos += 1;
+            }
+
+            if matches!(node, Node::Empty | Node::Assert(_)) {
+                return Err(format!("nothing to repeat at offset {start}"));
+            }
+
+            node = Node::Repeat {
+                node: Box::new(node),
+                min,
+                max,
+                greedy: !self.eat('?'),
+            };
+        }
+    }
+
+    /// Parses `{n}`, `{n,}` or `{n,m}`, a `{` not followed by counts is a
+    /// literal
+    fn parse_counts(&mut self) -> SyntaxResult<Option<(usize, Option<usize>)>> {
+        let start = self.pos;
+        self.pos += 1;
+        let min = self.parse_number();
+        let max = match (min, self.eat(',')) {
+            (Some(min), false) => Some(min),
+            (Some(_), true) => self.parse_number(),
+            (None, _) => {
+                self.pos = start;
+                return Ok(None);
+            }
+        };
+
+        if !self.eat('}') {
+            self.pos = start;
+            return Ok(None);
+        }
+
+        let min = min.unwrap_or(0);
+
+        if max.is_some_and(|max| max < min) {
+            return Err(format!("invalid repetition count at offset {start}"));
+        }
+
+        Ok(Some((min, max)))
+    }
+
+    fn parse_number(&mut self) -> Option<usize> {
+        let start = self.pos;
+
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+
+        self.chars[start..self.pos]
+            .iter()
+            .collect::<String>()
+            .parse()
+            .ok()
+    }
+
+    fn parse_atom(&mut self) -> SyntaxResult<Node> {
+        let offset = self.pos;
+
+        match self.next().expect("Called with remaining input") {
+            '.' => Ok(Node::Any),
+            '^' => Ok(Node::Assert(Assertion::Start)),
+            '$' => Ok(Node::Assert(Assertion::End)),
+            '[' => self.parse_class().map(Node::Class),
+            '(' => {
+                let index = if self.eat('?') {
+                    if !self.eat(':') {
+                        return Err(format!("unsupported group syntax at offset {offset}"));
+                    }
+
+                    None
+                } else {
+                    self.groups += 1;
+                    Some(self.groups - 1)
+                };
+                let node = self.parse_alternation()?;
+
+                if !self.eat(')') {
+                    return Err(format!("unclosed group at offset {offset}"));
+                }
+
+                Ok(Node::Group(Box::new(node), index))
+            }
+            '\\' => self.parse_escape(),
+            c @ ('*' | '+' | '?') => {
+                Err(format!("nothing to repeat before '{c}' at offset {offset}"))
+            }
+            c => Ok(Node::Literal(c)),
+        }
+    }
+
+    fn parse_escape(&mut self) -> SyntaxResult<Node> {
+        let shorthand = |ranges: &[(char, char)], negated| {
+            Node::Class(Class {
+                ranges: ranges.to_vec(),
+                negated,
+            })
+        };
+
+        match self.next() {
+            Some('d') => Ok(shorthand(DIGIT, false)),
+            Some('D') => Ok(shorthand(DIGIT, true)),
+            Some('w') => Ok(shorthand(WORD, false)),
+            Some('W') => Ok(shorthand(WORD, true)),
+            Some('s') => Ok(shorthand(SPACE, false)),
+            Some('S') => Ok(shorthand(SPACE, true)),
+            Some('b') => Ok(Node::Assert(Assertion::WordBoundary)),
+            Some('B') => Ok(Node::Assert(Assertion::NotWordBoundary)),
+            Some(_) => {
+                self.pos -= 1;
+                self.parse_escaped_char().map(Node::Literal)
+            }
+            None => Err("trailing '\\'".to_owned()),
+        }
+    }
+
+    /// Parses the character following a `\`
+    fn parse_escaped_char(&mut self) -> SyntaxResult<char> {
+        let offset = self.pos;
+
+        match self.next() {
+            Some('n') => Ok('\n'),
+            Some('r') => Ok('\r'),
+            Some('t') => Ok('\t'),
+            Some('0') => Ok('\0'),
+            Some('x') => {
+                let digits = self
+                    .chars
+                    .get(self.pos..self.pos + 2)
+                    .filter(|digits| digits.iter().all(char::is_ascii_hexdigit))
+                    .ok_or_else(|| format!("invalid hexadecimal escape at offset {offset}"))?;
+                let code = digits.iter().fold(0, |code, digit| {
+                    code * 16 + digit.to_digit(16).expect("Checked to be hexadecimal")
+                });
+                self.pos += 2;
+                Ok(char::from_u32(code).expect("Two hexadecimal digits are a valid char"))
+            }
+            Some(c) if !c.is_alphanumeric() => Ok(c),
+            Some(c) => Err(format!("unsupported escape '\\{c}' at offset {offset}")),
+            None => Err("trailing '\\'".to_owned()),
+        }
+    }
+
+    fn parse_class(&mut self) -> SyntaxResult<Class> {
+        let offset = self.pos - 1;
+        let negated = self.eat('^');
+        let mut ranges = Vec::new();
+        let mut first = true;
+
+        loop {
+            let start = match self.next() {
+                None => return Err(format!("unclosed class at offset {offset}")),
+                Some(']') if !first => break,
+                Some('\\') => match self.peek() {
+                    Some('d') => {
+                        self.pos += 1;
+                        ranges.extend_from_slice(DIGIT);
+                        continue;
+                    }
+                    Some('w') => {
+                        self.pos += 1;
+                        ranges.extend_from_slice(WORD);
+                        continue;
+                    }
+                    Some('s') => {
+                        self.pos += 1;
+                        ranges.extend_from_slice(SPACE);
+                        continue;
+                    }
+                    _ => self.parse_escaped_char()?,
+                },
+                Some(c) => c,
+            };
+            first = false;
+
+            let end = if self.peek() == Some('-') && self.chars.get(self.pos + 1) != Some(&']') {
+                self.pos += 1;
+
+                match self.next() {
+                    Some('\\') => self.parse_escaped_char()?,
+                    Some(c) => c,
+                    None => return Err(format!("unclosed class at offset {offset}")),
+                }
+            } else {
+                start
+            };
+
+            if end < start {
+                return Err(format!("invalid class range at offset {offset}"));
+            }
+
+            ranges.push((start, end));
+        }
+
+        Ok(Class { ranges, negated })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Inst {
+    Char(char),
+    Any,
+    Class(Class),
+    Assert(Assertion),
+    /// Continues at the first target, backtracking to the second one
+    Split(usize, usize),
+    Jump(usize),
+    /// Records the current position in a capture slot
+    Save(usize),
+    Match,
+}
+
+struct Compiler {
+    program: Vec<Inst>,
+}
+
+impl Compiler {
+    fn emit(&mut self, inst: Inst) -> SyntaxResult<usize> {
+        if self.program.len() >= MAX_PROGRAM_LEN {
+            return Err("pattern is too large".to_owned());
+        }
+
+        self.program.push(inst);
+        Ok(self.program.len() - 1)
+    }
+
+    fn compile(&mut self, node: &Node) -> SyntaxResult<()> {
+        match node {
+            Node::Empty => {}
+            Node::Literal(c) => {
+                self.emit(Inst::Char(*c))?;
+            }
+            Node::Any => {
+                self.emit(Inst::Any)?;
+            }
+            Node::Class(class) => {
+                self.emit(Inst::Class(class.clone()))?;
+            }
+            Node::Assert(assertion) => {
+                self.emit(Inst::Assert(*assertion))?;
+            }
+            Node::Group(node, None) => self.compile(node)?,
+            Node::Group(node, Some(index)) => {
+                self.emit(Inst::Save(index * 2))?;
+                self.compile(node)?;
+                self.emit(Inst::Save(index * 2 + 1))?;
+            }
+            Node::Concat(nodes) => {
+                for node in nodes {
+                    self.compile(node)?;
+                }
+            }
+            Node::Alternate(nodes) => {
+                let mut jumps = Vec::new();
+
+                for (index, node) in nodes.iter().enumerate() {
+                    if index == nodes.len() - 1 {
+                        self.compile(node)?;
+                        break;
+                    }
+
+                    let split = self.emit(Inst::Split(0, 0))?;
+                    self.compile(node)?;
+                    jumps.push(self.emit(Inst::Jump(0))?);
+                    self.program[split] = Inst::Split(split + 1, self.program.len());
+                }
+
+                let end = self.program.len();
+
+                for jump in jumps {
+                    self.program[jump] = Inst::Jump(end);
+                }
+            }
+            Node::Repeat {
+                node,
+                min,
+                max,
+                greedy,
+            } => {
+                for _ in 0..*min {
+                    self.compile(node)?;
+                }
+
+                match max {
+                    None => {
+                        let split = self.emit(Inst::Split(0, 0))?;
+                        self.compile(node)?;
+                        self.emit(Inst::Jump(split))?;
+                        self.program[split] = self.split(split + 1, self.program.len(), *greedy);
+                    }
+                    Some(max) => {
+                        let mut splits = Vec::new();
+
+                        for _ in *min..*max {
+                            splits.push(self.emit(Inst::Split(0, 0))?);
+                            self.compile(node)?;
+                        }
+
+                        let end = self.program.len();
+
+                        for split in splits {
+                            self.program[split] = self.split(split + 1, end, *greedy);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn split(&self, body: usize, exit: usize, greedy: bool) -> Inst {
+        if greedy {
+            Inst::Split(body, exit)
+        } else {
+            Inst::Split(exit, body)
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Job {
+    Inst {
+        pc: usize,
+        pos: usize,
+    },
+    RestoreSlot {
+        slot: usize,
+        position: Option<usize>,
+    },
+}
+
+/// State of a bounded backtracking search
+struct Search<'a> {
+    program: &'a [Inst],
+    chars: &'a [char],
+    /// Character preceding `chars`, checked by word boundaries
+    before: Option<char>,
+    /// Whether `chars` starts at the beginning of the text, where `^` holds
+    at_start: bool,
+    /// Whether `visited` is used, `sparse` otherwise
+    dense: bool,
+    /// Bitset of the `(instruction, position)` pairs already explored
+    visited: &'a mut Vec<u64>,
+    /// Pairs already explored when a bitset would be too large, whose size is
+    /// bounded by the work done by the search
+    sparse: &'a mut HashSet<usize>,
+    /// Character positions of the capture groups
+    slots: &'a mut Vec<Option<usize>>,
+    stack: &'a mut Vec<Job>,
+    /// Whether the current attempt looked at the end of the text
+    reached_end: bool,
+}
+
+impl Search<'_> {
+    /// Tries to match starting at `start`, leaving the captures in `slots`
+    fn run(&mut self, start: usize) -> bool {
+        self.stack.clear();
+        self.stack.push(Job::Inst { pc: 0, pos: start });
+
+        while let Some(job) = self.stack.pop() {
+            let (mut pc, mut pos) = match job {
+                Job::Inst { pc, pos } => (pc, pos),
+                Job::RestoreSlot { slot, position } => {
+                    self.slots[slot] = position;
+                    continue;
+                }
+            };
+
+            loop {
+                if pos == self.chars.len() {
+                    self.reached_end = true;
+                }
+
+                if !self.visit(pc * (self.chars.len() + 1) + pos) {
+                    break;
+                }
+
+                let current = self.chars.get(pos).copied();
+
+                match self.program[pc] {
+                    Inst::Char(c) if current == Some(c) => {
+                        pc += 1;
+                        pos += 1;
+                    }
+                    Inst::Any if current.is_some_and(|c| c != '\n') => {
+                        pc += 1;
+                        pos += 1;
+                    }
+                    Inst::Class(ref class) if current.is_some_and(|c| class.matches(c)) => {
+                        pc += 1;
+                        pos += 1;
+                    }
+                    Inst::Assert(assertion) if self.holds(assertion, pos) => pc += 1,
+                    Inst::Split(first, second) => {
+                        self.stack.push(Job::Inst { pc: second, pos });
+                        pc = first;
+                    }
+                    Inst::Jump(target) => pc = target,
+                    Inst::Save(slot) => {
+                        self.stack.push(Job::RestoreSlot {
+                            slot,
+                            position: self.slots[slot],
+                        });
+                        self.slots[slot] = Some(pos);
+                        pc += 1;
+                    }
+                    Inst::Match => return true,
+                    _ => break,
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Marks the state `bit` as explored, returning `false` if it already was
+    fn visit(&mut self, bit: usize) -> bool {
+        if !self.dense {
+            return self.sparse.insert(bit);
+        }
+
+        let word = &mut self.visited[bit / 64];
+        let mask = 1 << (bit % 64);
+        let unvisited = *word & mask == 0;
+        *word |= mask;
+        unvisited
+    }
+
+    fn holds(&self, assertion: Assertion, pos: usize) -> bool {
+        let before = match pos.checked_sub(1) {
+            Some(pos) => self.chars.get(pos).copied(),
+            None => self.before,
+        }
+        .is_some_and(is_word);
+        let after = self.chars.get(pos).is_some_and(|&c| is_word(c));
+
+        match assertion {
+            Assertion::Start => pos == 0 && self.at_start,
+            Assertion::End => pos == self.chars.len(),
+            Assertion::WordBoundary => before != after,
+            Assertion::NotWordBoundary => before == after,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find<'a>(pattern: &str, text: &'a str) -> Option<Vec<Option<&'a str>>> {
+        let pattern = Pattern::new(pattern).expect("Failed to compile pattern");
+        let groups = pattern.search(text)?;
+        Some(
+            groups
+                .into_iter()
+                .map(|group| group.map(|(start, end)| &text[start..end]))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn matches_literals_and_classes() {
+        assert_eq!(find("b.d", "abcde"), Some(vec![Some("bcd")]));
+        assert_eq!(find(r"\d+", "port 8080!"), Some(vec![Some("8080")]));
+        assert_eq!(find("[^a-c ]+", "abc déf"), Some(vec![Some("déf")]));
+        assert_eq!(
+            find(r"[\w.-]+@", "mail: x.y-z@host"),
+            Some(vec![Some("x.y-z@")])
+        );
+        assert_eq!(find(r"\$ $", "~ $ ls\n~ $ "), Some(vec![Some("$ ")]));
+        assert_eq!(find(r"\x1b\[0m", "\x1b[0m"), Some(vec![Some("\x1b[0m")]));
+        assert_eq!(find("^b", "ab"), None);
+        assert_eq!(find(r"\bcat\b", "concat cat"), Some(vec![Some("cat")]));
+    }
+
+    #[test]
+    fn captures_groups() {
+        assert_eq!(
+            find(r"(\w+)@(\w+)(?:\.(com|org))?", "to: root@example.org"),
+            Some(vec![
+                Some("root@example.org"),
+                Some("root"),
+                Some("example"),
+                Some("org")
+            ])
+        );
+        assert_eq!(find("(a)|(b)", "b"), Some(vec![Some("b"), None, Some("b")]));
+    }
+
+    #[test]
+    fn repeats() {
+        assert_eq!(find("<.+>", "<a><b>"), Some(vec![Some("<a><b>")]));
+        assert_eq!(find("<.+?>", "<a><b>"), Some(vec![Some("<a>")]));
+        assert_eq!(find("a{2,3}", "aaaa"), Some(vec![Some("aaa")]));
+        assert_eq!(find("a{2}", "a"), None);
+        assert_eq!(find("x{,2}", "x{,2}"), Some(vec![Some("x{,2}")]));
+        assert_eq!(find("(ab)+", "ababx"), Some(vec![Some("abab"), Some("ab")]));
+        // Repeating an empty match terminates
+        assert_eq!(
+            find("(a*)*b", "aaab").map(|groups| groups[0]),
+            Some(Some("aaab"))
+        );
+        // Backtracking stays linear on patterns which are usually exponential
+        let text = "a".repeat(5000);
+        assert_eq!(find("(a|aa)*c", &text), None);
+    }
+
+    #[test]
+    fn resumes_searches() {
+        let pattern = Pattern::new(r"ab+c").unwrap();
+        let mut scratch = Scratch::default();
+        // Only the trailing `a` may start a match once text is appended
+        assert_eq!(
+            pattern.search_from("xxa", 0, &mut scratch),
+            Searched::NoMatch { resume: 2 }
+        );
+        assert_eq!(
+            pattern.search_from("xxabbc", 2, &mut scratch),
+            Searched::Match(vec![Some((2, 6))])
+        );
+
+        // Anchors and word boundaries still see the text before `from`
+        let pattern = Pattern::new(r"^b|\bc").unwrap();
+        assert_eq!(
+            pattern.search_from("abc", 1, &mut scratch),
+            Searched::NoMatch { resume: 3 }
+        );
+        assert_eq!(
+            pattern.search_from("é c", 2, &mut scratch),
+            Searched::Match(vec![Some((3, 4))])
+        );
+    }
+
+    #[test]
+    fn bounds_memory_of_large_searches() {
+        // A bitset would take over 100 MiB here
+        let pattern = Pattern::new(&"a".repeat(5000)).unwrap();
+        let text = "ab".repeat(100_000);
+        assert!(!pattern.is_match(&text));
+        assert!(pattern.is_match(&format!("{text}{}", "a".repeat(5000))));
+    }
+
+    #[test]
+    fn escapes_literals() {
+        let pattern = Pattern::literal("[y/N]? (1.5)");
+        assert!(pattern.is_match("Continue [y/N]? (1.5)"));
+        assert!(!pattern.is_match("Continue y? 125"));
+    }
+
+    #[test]
+    fn rejects_invalid_syntax() {
+        for pattern in [
+            "(a",
+            "a)",
+            "[a-",
+            "*a",
+            r"\q",
+            "a{3,1}",
+            "(?<name>a)",
+            "\\",
+            "a\\",
+        ] {
+            let err = Pattern::new(pattern).expect_err("Pattern should be invalid");
+            assert!(matches!(err, Error::InvalidPattern { .. }), "{pattern}");
+        }
+
+        assert!(Pattern::new("a{1000}{1000}").is_err());
+    }
+}
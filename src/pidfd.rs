@@ -0,0 +1,132 @@
+use std::io;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd};
+use std::time::{Duration, Instant};
+
+use crate::Signal;
+
+/// File descriptor referring to a process, obtained with `pidfd_open(2)`
+///
+/// Unlike a PID, a pidfd keeps referring to the same process after it exits,
+/// so it can not be confused with an unrelated process reusing the PID.
+#[derive(Debug)]
+pub(crate) struct PidFd(OwnedFd);
+
+impl PidFd {
+    /// Opens a pidfd for `pid`
+    ///
+    /// Fails with `ENOSYS` on kernels older than Linux 5.3.
+    pub(crate) fn open(pid: u32) -> io::Result<Self> {
+        let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid as libc::pid_t, 0) };
+
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self(unsafe { OwnedFd::from_raw_fd(fd as libc::c_int) }))
+    }
+
+    /// Delivers `signal` to the process using `pidfd_send_signal(2)`
+    ///
+    /// Fails with `ESRCH` once the process exited, even if its PID was
+    /// reused.
+    pub(crate) fn send_signal(&self, signal: Signal) -> io::Result<()> {
+        let result = unsafe {
+            libc::syscall(
+                libc::SYS_pidfd_send_signal,
+                self.0.as_raw_fd(),
+                signal.as_raw(),
+                std::ptr::null::<libc::siginfo_t>(),
+                0,
+            )
+        };
+
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Waits up to `timeout`, or forever when [`None`], for the process to
+    /// exit
+    ///
+    /// Returns `true` once the process exited, even if it was not reaped yet.
+    pub(crate) fn wait(&self, timeout: Option<Duration>) -> io::Result<bool> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+        loop {
+            let timeout_ms = match deadline {
+                Some(deadline) => deadline
+                    .saturating_duration_since(Instant::now())
+                    .as_millis()
+                    .min(libc::c_int::MAX as u128) as libc::c_int,
+                None => -1,
+            };
+            let mut pollfd = libc::pollfd {
+                fd: self.0.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+
+            match unsafe { libc::poll(&mut pollfd, 1, timeout_ms) } {
+                -1 => {
+                    let err = io::Error::last_os_error();
+
+                    if err.kind() != io::ErrorKind::Interrupted {
+                        return Err(err);
+                    }
+                }
+                0 if deadline.is_some_and(|deadline| Instant::now() >= deadline) => {
+                    return Ok(false);
+                }
+                0 => {}
+                _ => return Ok(true),
+            }
+        }
+    }
+}
+
+impl AsFd for PidFd {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::Command;
+
+    use super::*;
+
+    #[test]
+    fn waits_for_exit() {
+        let mut child = Command::new("sleep").arg("10").spawn().unwrap();
+        let pidfd = PidFd::open(child.id());
+
+        // Kernels older than 5.3 lack `pidfd_open`
+        if let Ok(ref pidfd) = pidfd {
+            assert!(!pidfd.wait(Some(Duration::from_millis(50))).unwrap());
+        }
+
+        child.kill().unwrap();
+
+        if let Ok(ref pidfd) = pidfd {
+            assert!(pidfd.wait(None).unwrap());
+        }
+
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn sends_signal() {
+        let mut child = Command::new("sleep").arg("10").spawn().unwrap();
+
+        match PidFd::open(child.id()) {
+            Ok(pidfd) => pidfd.send_signal(Signal::Kill).unwrap(),
+            Err(_) => child.kill().unwrap(),
+        }
+
+        assert_eq!(child.wait().unwrap().signal(), Some(libc::SIGKILL));
+    }
+}
@@ -0,0 +1,403 @@
+use std::borrow::Cow;
+use std::ffi::OsStr;
+use std::io::{self, Read};
+use std::process::{ChildStdin, ChildStdout, Stdio};
+use std::thread;
+
+use crate::{Detach, Error, ExitStatus, Process, ProcessBuilder, Result, Signal, output};
+
+/// Chain of programs connected like `a | b | c` in a shell
+///
+/// The stdout of every stage is connected to the stdin of the next one with
+/// a pipe. Arguments are passed to each program as is, no shell is
+/// involved. Each stage is a [`ProcessBuilder`], so the environment, working
+/// directory, stderr and limits of every program can be configured
+/// separately. The stdin of the first stage and the stdout of the last one
+/// follow their configuration, by default stdin is connected to `/dev/null`
+/// and stdout is piped.
+///
+/// By default the stages are placed in a new process group led by the first
+/// one, which allows signalling the whole pipeline at once.
+///
+/// **Note:** The detachment configured on the stages is ignored, a session
+/// can only hold descendants of its leader so the stages can not share a
+/// new session.
+///
+/// # Example
+///
+/// ```ignore
+/// use xprocess::Pipeline;
+///
+/// let mut pipeline = Pipeline::new();
+/// pipeline.command("ps").arg("aux");
+/// pipeline.command("grep").arg("nginx");
+/// pipeline.command("wc").arg("-l");
+///
+/// let output = pipeline.pipefail(true).output().expect("Failed to run pipeline");
+/// println!("{} nginx processes", output.stdout_lossy().trim());
+/// ```
+#[derive(Debug)]
+pub struct Pipeline {
+    stages: Vec<ProcessBuilder>,
+    pipefail: bool,
+    new_process_group: bool,
+}
+
+impl Pipeline {
+    /// Creates an empty pipeline
+    pub fn new() -> Self {
+        Self {
+            stages: Vec::new(),
+            pipefail: false,
+            new_process_group: true,
+        }
+    }
+
+    /// Appends a stage running `cmd` and returns its [`ProcessBuilder`] for
+    /// further configuration
+    pub fn command<S: AsRef<OsStr>>(&mut self, cmd: S) -> &mut ProcessBuilder {
+        self.stages.push(ProcessBuilder::new(cmd));
+        self.stages.last_mut().expect("Stage was just added")
+    }
+
+    /// Appends a stage configured by `builder`
+    pub fn stage(&mut self, builder: ProcessBuilder) -> &mut Self {
+        self.stages.push(builder);
+        self
+    }
+
+    /// Reports the status of the rightmost stage which failed as the status
+    /// of the pipeline, like `set -o pipefail` in bash
+    ///
+    /// Disabled by default, in which case the status of the last stage is
+    /// reported.
+    pub fn pipefail(&mut self, pipefail: bool) -> &mut Self {
+        self.pipefail = pipefail;
+        self
+    }
+
+    /// Configures whether the stages are placed in a new process group,
+    /// enabled by default
+    ///
+    /// When disabled, the stages stay in the process group of the current
+    /// process, so signals generated by the terminal reach them.
+    pub fn new_process_group(&mut self, new_process_group: bool) -> &mut Self {
+        self.new_process_group = new_process_group;
+        self
+    }
+
+    /// Spawns every stage of the pipeline
+    ///
+    /// Fails with [`Error::EmptyPipeline`] if no stage was added. If a stage
+    /// can not be spawned, the stages spawned before it are killed.
+    pub fn spawn(&mut self) -> Result<RunningPipeline> {
+        let last = match self.stages.len() {
+            0 => return Err(Error::EmptyPipeline),
+            len => len - 1,
+        };
+        let mut processes: Vec<Process> = Vec::with_capacity(self.stages.len());
+        let mut stdin = None;
+
+        for (index, stage) in self.stages.iter().enumerate() {
+            let spawned = stage.pre_exec().and_then(|mut pre_exec| {
+                pre_exec.detach = if self.new_process_group {
+                    Detach::ProcessGroup
+                } else {
+                    Detach::None
+                };
+
+                // Later stages join the group led by the first one, which
+                // exists as the first stage is not reaped yet
+                if self.new_process_group
+                    && let Some(first) = processes.first()
+                {
+                    pre_exec.process_group = Some(first.pid());
+                }
+
+                let stdout = (index < last).then(Stdio::piped);
+                stage.spawn_with(pre_exec, stdin.take(), stdout)
+            });
+
+            let mut process = match spawned {
+                Ok(process) => process,
+                Err(err) => {
+                    for mut process in processes {
+                        process.signal(Signal::Kill).ok();
+                        process.wait().ok();
+                    }
+
+                    return Err(err);
+                }
+            };
+
+            if index < last {
+                stdin = process.take_stdout().map(Stdio::from);
+            }
+
+            processes.push(process);
+        }
+
+        Ok(RunningPipeline {
+            processes,
+            pipefail: self.pipefail,
+        })
+    }
+
+    /// Spawns the pipeline, waits for every stage to exit and collects the
+    /// output
+    ///
+    /// See [`RunningPipeline::wait_with_output`].
+    pub fn output(&mut self) -> Result<PipelineOutput> {
+        self.spawn()?.wait_with_output()
+    }
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Stages of a [`Pipeline`] which was spawned
+#[derive(Debug)]
+pub struct RunningPipeline {
+    /// Process of every stage, never empty
+    processes: Vec<Process>,
+    pipefail: bool,
+}
+
+impl RunningPipeline {
+    /// Returns the process of every stage, in order
+    pub fn processes(&self) -> &[Process] {
+        &self.processes
+    }
+
+    /// Returns the process of every stage, in order
+    pub fn processes_mut(&mut self) -> &mut [Process] {
+        &mut self.processes
+    }
+
+    /// Retrieves a writer for the stdin pipe of the first stage
+    ///
+    /// Returns [`None`] unless stdin of the first stage was configured with
+    /// [`StdioMode::Piped`].
+    ///
+    /// [`StdioMode::Piped`]: crate::StdioMode::Piped
+    pub fn stdin(&mut self) -> Option<&mut ChildStdin> {
+        self.processes[0].stdin()
+    }
+
+    /// Takes ownership of the stdout pipe of the last stage
+    pub fn take_stdout(&mut self) -> Option<ChildStdout> {
+        self.last_mut().take_stdout()
+    }
+
+    /// Delivers `signal` to every stage
+    ///
+    /// The signal is sent to the process group of the pipeline, or to each
+    /// stage which is still running when the stages were not placed in a new
+    /// process group.
+    pub fn signal(&self, signal: Signal) -> Result<()> {
+        match self.processes[0].signal_group(signal) {
+            Err(Error::NoProcessGroup { .. }) => {}
+            result => return result,
+        }
+
+        for process in &self.processes {
+            match process.signal(signal) {
+                Ok(()) | Err(Error::NoSuchProcess { .. }) => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Waits for every stage to exit and returns their statuses, in order
+    ///
+    /// The stdin handle of the first stage is closed before waiting.
+    pub fn wait_all(&mut self) -> Result<Vec<ExitStatus>> {
+        self.processes.iter_mut().map(Process::wait).collect()
+    }
+
+    /// Waits for every stage to exit and returns the status of the pipeline
+    ///
+    /// This is the status of the last stage, or of the rightmost stage which
+    /// failed when [`Pipeline::pipefail`] is enabled.
+    pub fn wait(&mut self) -> Result<ExitStatus> {
+        let statuses = self.wait_all()?;
+        Ok(pipeline_status(&statuses, self.pipefail))
+    }
+
+    /// Waits for every stage to exit while collecting the stdout of the last
+    /// stage and the stderr of every stage
+    ///
+    /// All pipes are drained concurrently, so no stage can deadlock on a full
+    /// pipe. Streams which are not piped, or were already taken, are
+    /// reported as empty.
+    pub fn wait_with_output(&mut self) -> Result<PipelineOutput> {
+        // Close stdin first, the first stage might be waiting for input
+        self.processes[0].close_stdin();
+
+        let (last, stages) = self
+            .processes
+            .split_last_mut()
+            .expect("Pipeline has stages");
+        let stderr_readers: Vec<_> = stages
+            .iter_mut()
+            .map(|process| {
+                process.take_stderr().map(|mut stderr| {
+                    thread::spawn(move || -> io::Result<Vec<u8>> {
+                        let mut buf = Vec::new();
+                        stderr.read_to_end(&mut buf)?;
+                        Ok(buf)
+                    })
+                })
+            })
+            .collect();
+        let (stdout, last_stderr) = output::read2(last.take_stdout(), last.take_stderr())?;
+
+        let mut stderr = Vec::with_capacity(self.processes.len());
+
+        for reader in stderr_readers {
+            stderr.push(match reader {
+                Some(handle) => handle
+                    .join()
+                    .map_err(|_| io::Error::other("stderr reader thread panicked"))??,
+                None => Vec::new(),
+            });
+        }
+
+        stderr.push(last_stderr);
+
+        let statuses = self.wait_all()?;
+
+        Ok(PipelineOutput {
+            status: pipeline_status(&statuses, self.pipefail),
+            statuses,
+            stdout,
+            stderr,
+        })
+    }
+
+    fn last_mut(&mut self) -> &mut Process {
+        self.processes.last_mut().expect("Pipeline has stages")
+    }
+}
+
+/// Output collected from a [`Pipeline`] which ran to completion
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PipelineOutput {
+    /// How the pipeline ended, see [`Pipeline::pipefail`]
+    pub status: ExitStatus,
+    /// How every stage ended, in order
+    pub statuses: Vec<ExitStatus>,
+    /// Bytes written by the last stage to stdout
+    pub stdout: Vec<u8>,
+    /// Bytes written by every stage to stderr, in order
+    pub stderr: Vec<Vec<u8>>,
+}
+
+impl PipelineOutput {
+    /// Decodes stdout as UTF-8, replacing invalid sequences with
+    /// `U+FFFD REPLACEMENT CHARACTER`
+    pub fn stdout_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.stdout)
+    }
+}
+
+/// Combines the statuses of the stages into the status of the pipeline
+fn pipeline_status(statuses: &[ExitStatus], pipefail: bool) -> ExitStatus {
+    let last = *statuses.last().expect("Pipeline has stages");
+
+    if !pipefail {
+        return last;
+    }
+
+    statuses
+        .iter()
+        .rev()
+        .find(|status| !status.success())
+        .copied()
+        .unwrap_or(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn connects_stages() {
+        let mut pipeline = Pipeline::new();
+        pipeline.command("cat").stdin_bytes("b\nc\na\n");
+        pipeline.command("sort");
+        pipeline.command("head").args(["-n", "2"]);
+
+        let output = pipeline.output().expect("Failed to run pipeline");
+        assert_eq!(output.stdout, b"a\nb\n");
+        assert_eq!(output.statuses.len(), 3);
+        assert!(output.status.success());
+    }
+
+    #[test]
+    fn reports_stage_statuses() {
+        let mut pipeline = Pipeline::new();
+        pipeline
+            .command("sh")
+            .args(["-c", "echo out; echo err >&2; exit 3"]);
+        pipeline.command("cat");
+
+        let output = pipeline.output().expect("Failed to run pipeline");
+        assert_eq!(
+            output.statuses,
+            [ExitStatus::Exited(3), ExitStatus::Exited(0)]
+        );
+        assert!(output.status.success());
+        assert_eq!(output.stdout_lossy(), "out\n");
+        assert_eq!(output.stderr, [b"err\n".to_vec(), Vec::new()]);
+
+        let output = pipeline
+            .pipefail(true)
+            .output()
+            .expect("Failed to run pipeline");
+        assert_eq!(output.status, ExitStatus::Exited(3));
+    }
+
+    #[test]
+    fn shares_process_group() {
+        let mut pipeline = Pipeline::new();
+        pipeline.command("sleep").arg("10");
+        pipeline.command("cat");
+        let mut running = pipeline.spawn().expect("Failed to spawn pipeline");
+
+        let leader = running.processes()[0].pid();
+
+        for process in running.processes() {
+            assert_eq!(process.pgid().expect("Failed to get PGID"), leader);
+        }
+
+        running
+            .signal(Signal::Term)
+            .expect("Failed to signal pipeline");
+        let statuses = running.wait_all().expect("Failed to wait for pipeline");
+        assert_eq!(statuses[0].signal(), Some(libc::SIGTERM));
+        assert_eq!(statuses[1].signal(), Some(libc::SIGTERM));
+    }
+
+    #[test]
+    fn kills_spawned_stages_on_failure() {
+        let mut pipeline = Pipeline::new();
+        pipeline.command("sleep").arg("10");
+        pipeline.command("xprocess-missing-program");
+
+        let started = std::time::Instant::now();
+        let err = pipeline.spawn().expect_err("Spawning should fail");
+        assert!(matches!(err, Error::NotFound { .. }));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let err = Pipeline::new().spawn().expect_err("Pipeline is empty");
+        assert!(matches!(err, Error::EmptyPipeline));
+    }
+}
@@ -0,0 +1,216 @@
+use std::ffi::CStr;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd};
+use std::os::unix::fs::OpenOptionsExt;
+
+/// Size of a terminal in character cells
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct WindowSize {
+    pub rows: u16,
+    pub cols: u16,
+}
+
+impl WindowSize {
+    /// Creates a window size of `rows` lines by `cols` columns
+    pub fn new(rows: u16, cols: u16) -> Self {
+        Self { rows, cols }
+    }
+}
+
+impl Default for WindowSize {
+    /// Classic 24 lines by 80 columns terminal
+    fn default() -> Self {
+        Self::new(24, 80)
+    }
+}
+
+/// Master side of the pseudo-terminal of a [`Process`] spawned with
+/// [`ProcessBuilder::pty`]
+///
+/// Reading returns everything the process writes to its terminal, stdout
+/// and stderr interleaved, while writing sends input as if it was typed.
+/// The terminal is in canonical mode with echo enabled, so input is echoed
+/// back and newlines are written as `\r\n`. Reading returns end of file once
+/// every process holding the terminal exited.
+///
+/// [`Process`]: crate::Process
+/// [`ProcessBuilder::pty`]: crate::ProcessBuilder::pty
+#[derive(Debug)]
+pub struct Pty {
+    master: File,
+}
+
+impl Pty {
+    /// Allocates a pseudo-terminal of `size` and returns its master along
+    /// with the opened terminal for the child
+    pub(crate) fn open(size: WindowSize) -> io::Result<(Self, File)> {
+        let fd = unsafe { libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY) };
+
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let master = unsafe { OwnedFd::from_raw_fd(fd) };
+        set_cloexec(&master)?;
+
+        if unsafe { libc::grantpt(fd) } < 0 || unsafe { libc::unlockpt(fd) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let pty = Self {
+            master: File::from(master),
+        };
+        pty.set_window_size(size)?;
+
+        let terminal = File::options()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NOCTTY)
+            .open(pty.terminal_path()?)?;
+
+        Ok((pty, terminal))
+    }
+
+    /// Path of the terminal device, e.g. `/dev/pts/3`
+    #[cfg(target_os = "linux")]
+    fn terminal_path(&self) -> io::Result<String> {
+        let mut buf = [0; 64];
+
+        match unsafe { libc::ptsname_r(self.master.as_raw_fd(), buf.as_mut_ptr(), buf.len()) } {
+            0 => Ok(unsafe { CStr::from_ptr(buf.as_ptr()) }
+                .to_string_lossy()
+                .into_owned()),
+            errno => Err(io::Error::from_raw_os_error(errno)),
+        }
+    }
+
+    /// Path of the terminal device, e.g. `/dev/ttys003`
+    ///
+    /// `ptsname(3)` uses a static buffer, which is copied right away.
+    #[cfg(not(target_os = "linux"))]
+    fn terminal_path(&self) -> io::Result<String> {
+        let name = unsafe { libc::ptsname(self.master.as_raw_fd()) };
+
+        if name.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(unsafe { CStr::from_ptr(name) }
+            .to_string_lossy()
+            .into_owned())
+    }
+
+    /// Returns the current size of the terminal
+    pub fn window_size(&self) -> io::Result<WindowSize> {
+        let mut winsize: libc::winsize = unsafe { std::mem::zeroed() };
+
+        if unsafe { libc::ioctl(self.master.as_raw_fd(), libc::TIOCGWINSZ, &mut winsize) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(WindowSize::new(winsize.ws_row, winsize.ws_col))
+    }
+
+    /// Resizes the terminal, which delivers `SIGWINCH` to its foreground
+    /// process group
+    pub fn set_window_size(&self, size: WindowSize) -> io::Result<()> {
+        let winsize = libc::winsize {
+            ws_row: size.rows,
+            ws_col: size.cols,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        };
+
+        if unsafe { libc::ioctl(self.master.as_raw_fd(), libc::TIOCSWINSZ, &winsize) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+}
+
+impl Read for &Pty {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match (&self.master).read(buf) {
+            // Linux reports a closed terminal with `EIO` instead of end of file
+            Err(err) if err.raw_os_error() == Some(libc::EIO) => Ok(0),
+            result => result,
+        }
+    }
+}
+
+impl Read for Pty {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&*self).read(buf)
+    }
+}
+
+impl Write for &Pty {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&self.master).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&self.master).flush()
+    }
+}
+
+impl Write for Pty {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&*self).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&*self).flush()
+    }
+}
+
+impl AsFd for Pty {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.master.as_fd()
+    }
+}
+
+/// Makes the terminal connected to stdin the controlling terminal of the
+/// calling process, which must be a session leader
+///
+/// Only calls `ioctl(2)`, so it is safe to use between `fork` and `exec`.
+pub(crate) fn set_controlling_terminal() -> io::Result<()> {
+    // The request is declared as `c_uint` on Apple platforms
+    if unsafe { libc::ioctl(libc::STDIN_FILENO, libc::TIOCSCTTY as _, 0) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Sets `FD_CLOEXEC`, which `posix_openpt(3)` does not accept as a flag on
+/// every platform
+fn set_cloexec(fd: &OwnedFd) -> io::Result<()> {
+    if unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_SETFD, libc::FD_CLOEXEC) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opens_terminal() {
+        let (pty, terminal) = Pty::open(WindowSize::new(40, 120)).expect("Failed to open pty");
+        assert_eq!(unsafe { libc::isatty(terminal.as_raw_fd()) }, 1);
+        assert_eq!(pty.window_size().unwrap(), WindowSize::new(40, 120));
+
+        pty.set_window_size(WindowSize::default()).unwrap();
+        assert_eq!(pty.window_size().unwrap(), WindowSize::new(24, 80));
+
+        // The master reports end of file once the terminal is closed
+        drop(terminal);
+        let mut buf = [0; 16];
+        assert_eq!((&pty).read(&mut buf).unwrap(), 0);
+    }
+}
@@ -0,0 +1,356 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::future::Future;
+use std::io::{self, Read, Write};
+use std::os::fd::{AsRawFd, BorrowedFd, RawFd};
+use std::pin::Pin;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Background thread waking up futures once file descriptors become ready
+/// or timers expire
+///
+/// Every pending future registers its interest and waker, the thread polls
+/// all of them with `poll(2)` and wakes the ones which are ready. This keeps
+/// the futures of this crate independent of any async runtime.
+struct Reactor {
+    registrations: Mutex<Registrations>,
+    /// Write end of the pipe interrupting `poll(2)` when registrations
+    /// change
+    notifier: File,
+}
+
+#[derive(Default)]
+struct Registrations {
+    next_id: u64,
+    entries: HashMap<u64, Registration>,
+}
+
+struct Registration {
+    fd: Option<RawFd>,
+    events: libc::c_short,
+    deadline: Option<Instant>,
+    waker: Waker,
+}
+
+/// Returns the reactor, starting its thread on first use
+fn reactor() -> &'static Reactor {
+    static REACTOR: OnceLock<Reactor> = OnceLock::new();
+
+    REACTOR.get_or_init(|| {
+        let (reader, writer) = io::pipe().expect("Failed to create the reactor pipe");
+        let reader = File::from(std::os::fd::OwnedFd::from(reader));
+        let notifier = File::from(std::os::fd::OwnedFd::from(writer));
+
+        for fd in [reader.as_raw_fd(), notifier.as_raw_fd()] {
+            set_nonblocking(fd).expect("Failed to configure the reactor pipe");
+        }
+
+        thread::Builder::new()
+            .name("xprocess-reactor".to_owned())
+            .spawn(move || run(reader))
+            .expect("Failed to spawn the reactor thread");
+
+        Reactor {
+            registrations: Mutex::default(),
+            notifier,
+        }
+    })
+}
+
+impl Reactor {
+    fn lock(&self) -> MutexGuard<'_, Registrations> {
+        // Registrations stay consistent even if a waker panicked
+        self.registrations
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Interrupts the `poll(2)` call of the reactor thread
+    fn notify(&self) {
+        // A full pipe already guarantees a wake-up
+        let _ = (&self.notifier).write(&[1]);
+    }
+}
+
+/// Loop of the reactor thread
+fn run(mut reader: File) {
+    let reactor = reactor();
+    let mut buf = [0; 64];
+
+    loop {
+        let mut pollfds = vec![libc::pollfd {
+            fd: reader.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        }];
+        let mut ids = Vec::new();
+        let mut deadline = None::<Instant>;
+
+        for (&id, registration) in &reactor.lock().entries {
+            if let Some(fd) = registration.fd {
+                pollfds.push(libc::pollfd {
+                    fd,
+                    events: registration.events,
+                    revents: 0,
+                });
+                ids.push(id);
+            }
+
+            if let Some(expires) = registration.deadline {
+                deadline = Some(deadline.map_or(expires, |deadline| deadline.min(expires)));
+            }
+        }
+
+        let timeout_ms = match deadline {
+            Some(deadline) => deadline
+                .saturating_duration_since(Instant::now())
+                .as_micros()
+                .div_ceil(1000)
+                .min(libc::c_int::MAX as u128) as libc::c_int,
+            None => -1,
+        };
+
+        // Errors are limited to `EINTR` and `ENOMEM`, both worth retrying
+        if unsafe {
+            libc::poll(
+                pollfds.as_mut_ptr(),
+                pollfds.len() as libc::nfds_t,
+                timeout_ms,
+            )
+        } < 0
+        {
+            continue;
+        }
+
+        if pollfds[0].revents != 0 {
+            while matches!(reader.read(&mut buf), Ok(len) if len > 0) {}
+        }
+
+        let now = Instant::now();
+        let mut ready = Vec::new();
+        let mut registrations = reactor.lock();
+
+        for (pollfd, id) in pollfds[1..].iter().zip(&ids) {
+            if pollfd.revents != 0
+                && let Some(registration) = registrations.entries.remove(id)
+            {
+                ready.push(registration.waker);
+            }
+        }
+
+        registrations.entries.retain(|_, registration| {
+            let expired = registration
+                .deadline
+                .is_some_and(|deadline| deadline <= now);
+
+            if expired {
+                ready.push(registration.waker.clone());
+            }
+
+            !expired
+        });
+        drop(registrations);
+
+        for waker in ready {
+            waker.wake();
+        }
+    }
+}
+
+/// Future completing once a file descriptor is ready or a deadline passed
+#[derive(Debug)]
+pub(crate) struct Readiness {
+    fd: Option<RawFd>,
+    events: libc::c_short,
+    deadline: Option<Instant>,
+    /// Identifier of the registration with the reactor, if any
+    id: Option<u64>,
+}
+
+/// Completes once `fd` can be read without blocking, or reached end of file
+pub(crate) fn readable(fd: BorrowedFd<'_>) -> Readiness {
+    Readiness::new(Some(fd.as_raw_fd()), libc::POLLIN, None)
+}
+
+/// Completes once `fd` can be written without blocking
+pub(crate) fn writable(fd: BorrowedFd<'_>) -> Readiness {
+    Readiness::new(Some(fd.as_raw_fd()), libc::POLLOUT, None)
+}
+
+/// Completes once `duration` elapsed
+pub(crate) fn sleep(duration: Duration) -> Readiness {
+    Readiness::new(None, 0, Some(Instant::now() + duration))
+}
+
+impl Readiness {
+    fn new(fd: Option<RawFd>, events: libc::c_short, deadline: Option<Instant>) -> Self {
+        Self {
+            fd,
+            events,
+            deadline,
+            id: None,
+        }
+    }
+
+    /// Checks whether the file descriptor is ready without blocking
+    fn is_ready(&self) -> io::Result<bool> {
+        let Some(fd) = self.fd else {
+            return Ok(false);
+        };
+        let mut pollfd = libc::pollfd {
+            fd,
+            events: self.events,
+            revents: 0,
+        };
+
+        match unsafe { libc::poll(&mut pollfd, 1, 0) } {
+            -1 => {
+                let err = io::Error::last_os_error();
+
+                match err.kind() {
+                    io::ErrorKind::Interrupted => Ok(false),
+                    _ => Err(err),
+                }
+            }
+            0 => Ok(false),
+            // Errors and hang-ups are reported by the next I/O operation
+            _ => Ok(true),
+        }
+    }
+
+    fn deregister(&mut self) {
+        if let Some(id) = self.id.take() {
+            reactor().lock().entries.remove(&id);
+        }
+    }
+}
+
+impl Future for Readiness {
+    type Output = io::Result<()>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let expired = self
+            .deadline
+            .is_some_and(|deadline| deadline <= Instant::now());
+
+        if expired || self.is_ready()? {
+            self.deregister();
+            return Poll::Ready(Ok(()));
+        }
+
+        let reactor = reactor();
+        let mut registrations = reactor.lock();
+
+        // The reactor drops registrations when waking them up
+        if let Some(registration) = self.id.and_then(|id| registrations.entries.get_mut(&id)) {
+            registration.waker.clone_from(cx.waker());
+            return Poll::Pending;
+        }
+
+        let id = registrations.next_id;
+        registrations.next_id += 1;
+        registrations.entries.insert(
+            id,
+            Registration {
+                fd: self.fd,
+                events: self.events,
+                deadline: self.deadline,
+                waker: cx.waker().clone(),
+            },
+        );
+        drop(registrations);
+        self.id = Some(id);
+        reactor.notify();
+
+        Poll::Pending
+    }
+}
+
+impl Drop for Readiness {
+    fn drop(&mut self) {
+        self.deregister();
+    }
+}
+
+/// Switches `fd` to non-blocking mode
+pub(crate) fn set_nonblocking(fd: RawFd) -> io::Result<()> {
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+
+    if flags < 0 || unsafe { libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Minimal executor used by tests, polling `future` on the current thread
+#[cfg(test)]
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    use std::sync::Arc;
+    use std::task::Wake;
+
+    struct Unparker(thread::Thread);
+
+    impl Wake for Unparker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Waker::from(Arc::new(Unparker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = std::pin::pin!(future);
+
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+
+        thread::park();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::fd::AsFd;
+
+    use super::*;
+
+    #[test]
+    fn wakes_up_on_readable_pipe() {
+        let (reader, mut writer) = io::pipe().unwrap();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            writer.write_all(b"ping").unwrap();
+        });
+
+        let started = Instant::now();
+        block_on(readable(reader.as_fd())).unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(40));
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn sleeps() {
+        let started = Instant::now();
+        block_on(sleep(Duration::from_millis(50))).unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn deregisters_dropped_futures() {
+        let (reader, _writer) = io::pipe().unwrap();
+        let mut future = Box::pin(readable(reader.as_fd()));
+        let waker = Waker::noop();
+        let mut cx = Context::from_waker(waker);
+        assert!(future.as_mut().poll(&mut cx).is_pending());
+
+        let id = future.id.expect("Future should be registered");
+        assert!(reactor().lock().entries.contains_key(&id));
+        drop(future);
+        assert!(!reactor().lock().entries.contains_key(&id));
+    }
+}
@@ -0,0 +1,100 @@
+use std::io;
+
+/// Type of the resource argument of `setrlimit(2)`, which differs between
+/// C libraries
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type RawResource = libc::__rlimit_resource_t;
+#[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+type RawResource = libc::c_int;
+
+/// Resources which can be capped with [`ProcessBuilder::rlimit`]
+///
+/// [`ProcessBuilder::rlimit`]: crate::ProcessBuilder::rlimit
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Resource {
+    /// Maximum size of the virtual memory in bytes (`RLIMIT_AS`)
+    AddressSpace,
+    /// Maximum CPU time in seconds, the process receives `SIGXCPU` once the
+    /// soft limit is reached (`RLIMIT_CPU`)
+    CpuTime,
+    /// Maximum number of open file descriptors (`RLIMIT_NOFILE`)
+    OpenFiles,
+    /// Maximum size of core dumps in bytes (`RLIMIT_CORE`)
+    CoreSize,
+    /// Maximum number of processes of the real user ID (`RLIMIT_NPROC`)
+    Processes,
+    /// Maximum size of files created by the process in bytes, the process
+    /// receives `SIGXFSZ` once the limit is reached (`RLIMIT_FSIZE`)
+    FileSize,
+    /// Maximum size of the stack in bytes (`RLIMIT_STACK`)
+    Stack,
+}
+
+impl Resource {
+    fn as_raw(self) -> RawResource {
+        match self {
+            Resource::AddressSpace => libc::RLIMIT_AS,
+            Resource::CpuTime => libc::RLIMIT_CPU,
+            Resource::OpenFiles => libc::RLIMIT_NOFILE,
+            Resource::CoreSize => libc::RLIMIT_CORE,
+            Resource::Processes => libc::RLIMIT_NPROC,
+            Resource::FileSize => libc::RLIMIT_FSIZE,
+            Resource::Stack => libc::RLIMIT_STACK,
+        }
+    }
+
+    /// Applies `limit` to the current process
+    ///
+    /// Only calls `setrlimit(2)`, so it is safe to use between `fork` and
+    /// `exec`.
+    pub(crate) fn set(self, limit: Rlimit) -> io::Result<()> {
+        let rlimit = libc::rlimit {
+            rlim_cur: limit
+                .soft
+                .map_or(libc::RLIM_INFINITY, |soft| soft as libc::rlim_t),
+            rlim_max: limit
+                .hard
+                .map_or(libc::RLIM_INFINITY, |hard| hard as libc::rlim_t),
+        };
+
+        if unsafe { libc::setrlimit(self.as_raw(), &rlimit) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+}
+
+/// Soft and hard limits of a [`Resource`], [`None`] meaning unlimited
+///
+/// The soft limit is the one enforced by the kernel, the process may raise
+/// it up to the hard limit. Lowering the hard limit is irreversible for
+/// unprivileged processes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rlimit {
+    pub soft: Option<u64>,
+    pub hard: Option<u64>,
+}
+
+impl Rlimit {
+    /// Creates a limit with distinct soft and hard values
+    pub fn new(soft: u64, hard: u64) -> Self {
+        Self {
+            soft: Some(soft),
+            hard: Some(hard),
+        }
+    }
+
+    /// Creates a limit using `limit` as both the soft and the hard value
+    pub fn fixed(limit: u64) -> Self {
+        Self::new(limit, limit)
+    }
+
+    /// Creates a limit without soft nor hard values
+    pub fn unlimited() -> Self {
+        Self {
+            soft: None,
+            hard: None,
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+/// Resources consumed by a [`Process`] over its lifetime, as reported by
+/// `wait4(2)`
+///
+/// [`Process`]: crate::Process
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ResourceUsage {
+    /// Time spent running in user mode
+    pub user_time: Duration,
+    /// Time spent running in kernel mode
+    pub system_time: Duration,
+    /// Peak resident set size in bytes
+    pub max_rss_bytes: u64,
+    /// Page faults serviced without any I/O
+    pub minor_page_faults: u64,
+    /// Page faults which required I/O
+    pub major_page_faults: u64,
+    /// Number of times the file system had to perform input
+    pub block_input_ops: u64,
+    /// Number of times the file system had to perform output
+    pub block_output_ops: u64,
+    /// Number of times the process gave up the CPU voluntarily, e.g. while
+    /// waiting for I/O
+    pub voluntary_context_switches: u64,
+    /// Number of times the process was preempted
+    pub involuntary_context_switches: u64,
+}
+
+impl From<libc::rusage> for ResourceUsage {
+    fn from(usage: libc::rusage) -> Self {
+        let counter = |value: libc::c_long| value.max(0) as u64;
+        let duration = |time: libc::timeval| {
+            Duration::new(time.tv_sec.max(0) as u64, time.tv_usec.max(0) as u32 * 1000)
+        };
+
+        // `ru_maxrss` is reported in kilobytes on Linux and in bytes on macOS
+        let max_rss_bytes = if cfg!(target_vendor = "apple") {
+            counter(usage.ru_maxrss)
+        } else {
+            counter(usage.ru_maxrss) * 1024
+        };
+
+        Self {
+            user_time: duration(usage.ru_utime),
+            system_time: duration(usage.ru_stime),
+            max_rss_bytes,
+            minor_page_faults: counter(usage.ru_minflt),
+            major_page_faults: counter(usage.ru_majflt),
+            block_input_ops: counter(usage.ru_inblock),
+            block_output_ops: counter(usage.ru_oublock),
+            voluntary_context_switches: counter(usage.ru_nvcsw),
+            involuntary_context_switches: counter(usage.ru_nivcsw),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_rusage() {
+        let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+        usage.ru_utime.tv_sec = 1;
+        usage.ru_utime.tv_usec = 500_000;
+        usage.ru_maxrss = 2048;
+        usage.ru_minflt = 7;
+        usage.ru_nvcsw = 3;
+
+        let usage = ResourceUsage::from(usage);
+        assert_eq!(usage.user_time, Duration::from_millis(1500));
+        assert_eq!(usage.system_time, Duration::ZERO);
+        assert_eq!(usage.minor_page_faults, 7);
+        assert_eq!(usage.voluntary_context_switches, 3);
+
+        if cfg!(target_vendor = "apple") {
+            assert_eq!(usage.max_rss_bytes, 2048);
+        } else {
+            assert_eq!(usage.max_rss_bytes, 2048 * 1024);
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use std::io;
+
+/// Scheduling policies for normal, non real-time, processes
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SchedPolicy {
+    /// Default time-sharing policy (`SCHED_OTHER`)
+    Other,
+    /// Time-sharing policy for CPU-bound jobs, which are slightly disfavored
+    /// and never preempt interactive processes (`SCHED_BATCH`)
+    Batch,
+    /// Runs only when the CPU would otherwise be idle (`SCHED_IDLE`)
+    Idle,
+}
+
+#[cfg(target_os = "linux")]
+impl SchedPolicy {
+    fn as_raw(self) -> libc::c_int {
+        match self {
+            SchedPolicy::Other => libc::SCHED_OTHER,
+            SchedPolicy::Batch => libc::SCHED_BATCH,
+            SchedPolicy::Idle => libc::SCHED_IDLE,
+        }
+    }
+}
+
+/// I/O scheduling class and priority, as used by `ioprio_set(2)`
+///
+/// Levels range from `0`, the highest priority, to `7`.
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum IoPriority {
+    /// Served before any other class, requires `CAP_SYS_ADMIN`
+    Realtime(u8),
+    /// Default class, sharing disk time according to the level
+    BestEffort(u8),
+    /// Served only when no other process needs the disk
+    Idle,
+}
+
+#[cfg(target_os = "linux")]
+impl IoPriority {
+    fn as_raw(self) -> libc::c_int {
+        // See `IOPRIO_PRIO_VALUE` in `linux/ioprio.h`
+        const CLASS_SHIFT: libc::c_int = 13;
+
+        match self {
+            IoPriority::Realtime(level) => (1 << CLASS_SHIFT) | level as libc::c_int,
+            IoPriority::BestEffort(level) => (2 << CLASS_SHIFT) | level as libc::c_int,
+            IoPriority::Idle => 3 << CLASS_SHIFT,
+        }
+    }
+}
+
+/// Sets the niceness of `pid`, or of the calling process when `0`
+pub(crate) fn set_nice(pid: libc::pid_t, nice: i32) -> io::Result<()> {
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, pid as libc::id_t, nice) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Restricts `pid`, or the calling process when `0`, to the given CPUs
+///
+/// Fails with `EINVAL` if no CPU is usable or if a CPU index exceeds
+/// `CPU_SETSIZE`. Does not allocate, so it is safe to use between `fork` and
+/// `exec`.
+#[cfg(target_os = "linux")]
+pub(crate) fn set_affinity(pid: libc::pid_t, cpus: &[usize]) -> io::Result<()> {
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+
+    for &cpu in cpus {
+        if cpu >= libc::CPU_SETSIZE as usize {
+            return Err(io::Error::from_raw_os_error(libc::EINVAL));
+        }
+
+        unsafe { libc::CPU_SET(cpu, &mut set) };
+    }
+
+    if unsafe { libc::sched_setaffinity(pid, size_of::<libc::cpu_set_t>(), &set) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Sets the scheduling policy of `pid`, or of the calling process when `0`
+#[cfg(target_os = "linux")]
+pub(crate) fn set_policy(pid: libc::pid_t, policy: SchedPolicy) -> io::Result<()> {
+    // Non real-time policies require a static priority of 0
+    let param: libc::sched_param = unsafe { std::mem::zeroed() };
+
+    if unsafe { libc::sched_setscheduler(pid, policy.as_raw(), &param) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Sets the I/O priority of `pid`, or of the calling process when `0`
+#[cfg(target_os = "linux")]
+pub(crate) fn set_io_priority(pid: libc::pid_t, priority: IoPriority) -> io::Result<()> {
+    const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_ioprio_set,
+            IOPRIO_WHO_PROCESS,
+            pid,
+            priority.as_raw(),
+        )
+    };
+
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Writes `score` to `/proc/<pid>/oom_score_adj`
+#[cfg(target_os = "linux")]
+pub(crate) fn set_oom_score_adj(pid: u32, score: i32) -> io::Result<()> {
+    std::fs::write(format!("/proc/{pid}/oom_score_adj"), score.to_string())
+}
+
+/// Writes `score` to `/proc/self/oom_score_adj`
+///
+/// Unlike [`set_oom_score_adj`] nothing is allocated, so it is safe to use
+/// between `fork` and `exec`.
+#[cfg(target_os = "linux")]
+pub(crate) fn set_own_oom_score_adj(score: i32) -> io::Result<()> {
+    let mut buf = [0; 11];
+    let value = format_decimal(score, &mut buf);
+    let fd = unsafe {
+        libc::open(
+            c"/proc/self/oom_score_adj".as_ptr(),
+            libc::O_WRONLY | libc::O_CLOEXEC,
+        )
+    };
+
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let written = unsafe { libc::write(fd, value.as_ptr().cast(), value.len()) };
+    let result = if written < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    };
+
+    unsafe { libc::close(fd) };
+    result
+}
+
+/// Formats `value` in decimal into `buf` without allocating
+#[cfg(target_os = "linux")]
+fn format_decimal(value: i32, buf: &mut [u8; 11]) -> &[u8] {
+    let mut remaining = value.unsigned_abs();
+    let mut start = buf.len();
+
+    loop {
+        start -= 1;
+        buf[start] = b'0' + (remaining % 10) as u8;
+        remaining /= 10;
+
+        if remaining == 0 {
+            break;
+        }
+    }
+
+    if value < 0 {
+        start -= 1;
+        buf[start] = b'-';
+    }
+
+    &buf[start..]
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_decimal() {
+        let mut buf = [0; 11];
+        assert_eq!(format_decimal(0, &mut buf), b"0");
+        assert_eq!(format_decimal(500, &mut buf), b"500");
+        assert_eq!(format_decimal(-1000, &mut buf), b"-1000");
+        assert_eq!(format_decimal(i32::MIN, &mut buf), b"-2147483648");
+    }
+
+    #[test]
+    fn encodes_io_priority() {
+        assert_eq!(IoPriority::BestEffort(4).as_raw(), 0x4004);
+        assert_eq!(IoPriority::Realtime(0).as_raw(), 0x2000);
+        assert_eq!(IoPriority::Idle.as_raw(), 0x6000);
+    }
+}
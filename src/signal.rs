@@ -0,0 +1,225 @@
+use std::fmt;
+
+use crate::{Error, Result};
+
+/// Standard POSIX signals that can be delivered to a [`Process`]
+///
+/// [`Process`]: crate::Process
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Signal {
+    /// Hangup detected on controlling terminal (`SIGHUP`)
+    Hup,
+    /// Interrupt from keyboard (`SIGINT`)
+    Int,
+    /// Quit from keyboard (`SIGQUIT`)
+    Quit,
+    /// Illegal instruction (`SIGILL`)
+    Ill,
+    /// Trace/breakpoint trap (`SIGTRAP`)
+    Trap,
+    /// Abort signal (`SIGABRT`)
+    Abrt,
+    /// Bus error (`SIGBUS`)
+    Bus,
+    /// Floating-point exception (`SIGFPE`)
+    Fpe,
+    /// Kill signal, cannot be caught or ignored (`SIGKILL`)
+    Kill,
+    /// User-defined signal 1 (`SIGUSR1`)
+    Usr1,
+    /// Invalid memory reference (`SIGSEGV`)
+    Segv,
+    /// User-defined signal 2 (`SIGUSR2`)
+    Usr2,
+    /// Broken pipe (`SIGPIPE`)
+    Pipe,
+    /// Timer signal from `alarm` (`SIGALRM`)
+    Alrm,
+    /// Termination signal (`SIGTERM`)
+    Term,
+    /// Child stopped or terminated (`SIGCHLD`)
+    Chld,
+    /// Continue if stopped (`SIGCONT`)
+    Cont,
+    /// Stop process, cannot be caught or ignored (`SIGSTOP`)
+    Stop,
+    /// Stop typed at terminal (`SIGTSTP`)
+    Tstp,
+    /// Terminal input for background process (`SIGTTIN`)
+    Ttin,
+    /// Terminal output for background process (`SIGTTOU`)
+    Ttou,
+    /// Urgent condition on socket (`SIGURG`)
+    Urg,
+    /// CPU time limit exceeded (`SIGXCPU`)
+    Xcpu,
+    /// File size limit exceeded (`SIGXFSZ`)
+    Xfsz,
+    /// Virtual alarm clock (`SIGVTALRM`)
+    Vtalrm,
+    /// Profiling timer expired (`SIGPROF`)
+    Prof,
+    /// Window resize signal (`SIGWINCH`)
+    Winch,
+    /// I/O now possible (`SIGIO`)
+    Io,
+    /// Bad system call (`SIGSYS`)
+    Sys,
+}
+
+impl Signal {
+    /// Every signal supported by [`Signal`]
+    pub const ALL: [Signal; 29] = [
+        Signal::Hup,
+        Signal::Int,
+        Signal::Quit,
+        Signal::Ill,
+        Signal::Trap,
+        Signal::Abrt,
+        Signal::Bus,
+        Signal::Fpe,
+        Signal::Kill,
+        Signal::Usr1,
+        Signal::Segv,
+        Signal::Usr2,
+        Signal::Pipe,
+        Signal::Alrm,
+        Signal::Term,
+        Signal::Chld,
+        Signal::Cont,
+        Signal::Stop,
+        Signal::Tstp,
+        Signal::Ttin,
+        Signal::Ttou,
+        Signal::Urg,
+        Signal::Xcpu,
+        Signal::Xfsz,
+        Signal::Vtalrm,
+        Signal::Prof,
+        Signal::Winch,
+        Signal::Io,
+        Signal::Sys,
+    ];
+
+    /// Retrieves the platform specific number for this signal
+    pub fn as_raw(self) -> i32 {
+        match self {
+            Signal::Hup => libc::SIGHUP,
+            Signal::Int => libc::SIGINT,
+            Signal::Quit => libc::SIGQUIT,
+            Signal::Ill => libc::SIGILL,
+            Signal::Trap => libc::SIGTRAP,
+            Signal::Abrt => libc::SIGABRT,
+            Signal::Bus => libc::SIGBUS,
+            Signal::Fpe => libc::SIGFPE,
+            Signal::Kill => libc::SIGKILL,
+            Signal::Usr1 => libc::SIGUSR1,
+            Signal::Segv => libc::SIGSEGV,
+            Signal::Usr2 => libc::SIGUSR2,
+            Signal::Pipe => libc::SIGPIPE,
+            Signal::Alrm => libc::SIGALRM,
+            Signal::Term => libc::SIGTERM,
+            Signal::Chld => libc::SIGCHLD,
+            Signal::Cont => libc::SIGCONT,
+            Signal::Stop => libc::SIGSTOP,
+            Signal::Tstp => libc::SIGTSTP,
+            Signal::Ttin => libc::SIGTTIN,
+            Signal::Ttou => libc::SIGTTOU,
+            Signal::Urg => libc::SIGURG,
+            Signal::Xcpu => libc::SIGXCPU,
+            Signal::Xfsz => libc::SIGXFSZ,
+            Signal::Vtalrm => libc::SIGVTALRM,
+            Signal::Prof => libc::SIGPROF,
+            Signal::Winch => libc::SIGWINCH,
+            Signal::Io => libc::SIGIO,
+            Signal::Sys => libc::SIGSYS,
+        }
+    }
+
+    /// Looks up the [`Signal`] matching a platform specific signal number
+    pub fn from_raw(signum: i32) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|signal| signal.as_raw() == signum)
+    }
+
+    /// Retrieves the conventional name of the signal, e.g. `SIGTERM`
+    pub fn name(self) -> &'static str {
+        match self {
+            Signal::Hup => "SIGHUP",
+            Signal::Int => "SIGINT",
+            Signal::Quit => "SIGQUIT",
+            Signal::Ill => "SIGILL",
+            Signal::Trap => "SIGTRAP",
+            Signal::Abrt => "SIGABRT",
+            Signal::Bus => "SIGBUS",
+            Signal::Fpe => "SIGFPE",
+            Signal::Kill => "SIGKILL",
+            Signal::Usr1 => "SIGUSR1",
+            Signal::Segv => "SIGSEGV",
+            Signal::Usr2 => "SIGUSR2",
+            Signal::Pipe => "SIGPIPE",
+            Signal::Alrm => "SIGALRM",
+            Signal::Term => "SIGTERM",
+            Signal::Chld => "SIGCHLD",
+            Signal::Cont => "SIGCONT",
+            Signal::Stop => "SIGSTOP",
+            Signal::Tstp => "SIGTSTP",
+            Signal::Ttin => "SIGTTIN",
+            Signal::Ttou => "SIGTTOU",
+            Signal::Urg => "SIGURG",
+            Signal::Xcpu => "SIGXCPU",
+            Signal::Xfsz => "SIGXFSZ",
+            Signal::Vtalrm => "SIGVTALRM",
+            Signal::Prof => "SIGPROF",
+            Signal::Winch => "SIGWINCH",
+            Signal::Io => "SIGIO",
+            Signal::Sys => "SIGSYS",
+        }
+    }
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Delivers `signal` to `pid` using `kill(2)`
+///
+/// Negative values of `pid` target the process group `-pid`, the error still
+/// reports the absolute value.
+pub(crate) fn send(pid: libc::pid_t, signal: Signal) -> Result<()> {
+    if unsafe { libc::kill(pid, signal.as_raw()) } == 0 {
+        return Ok(());
+    }
+
+    Err(Error::last_os_error(pid.unsigned_abs(), "signal"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_raw_numbers() {
+        for signal in Signal::ALL {
+            assert_eq!(Signal::from_raw(signal.as_raw()), Some(signal));
+        }
+        assert_eq!(Signal::from_raw(libc::SIGKILL), Some(Signal::Kill));
+        assert_eq!(Signal::from_raw(0), None);
+    }
+
+    #[test]
+    fn displays_name() {
+        assert_eq!(Signal::Term.to_string(), "SIGTERM");
+        assert_eq!(Signal::Kill.name(), "SIGKILL");
+    }
+
+    #[test]
+    fn reports_missing_process() {
+        // PIDs are capped well below `pid_t::MAX` on every supported platform
+        let result = send(libc::pid_t::MAX, Signal::Term);
+        assert!(matches!(result, Err(Error::NoSuchProcess { .. })));
+    }
+}
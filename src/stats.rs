@@ -0,0 +1,308 @@
+use std::fs;
+use std::io;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{Error, Result};
+
+/// Scheduling state of a process as reported by `/proc/<pid>/stat`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ProcessState {
+    /// Running or runnable (`R`)
+    Running,
+    /// Interruptible sleep (`S`)
+    Sleeping,
+    /// Uninterruptible sleep, usually waiting on I/O (`D`)
+    DiskSleep,
+    /// Exited but not reaped by its parent yet (`Z`)
+    Zombie,
+    /// Stopped by a signal (`T`)
+    Stopped,
+    /// Stopped by a debugger (`t`)
+    TracingStop,
+    /// Dead (`X`)
+    Dead,
+    /// Idle kernel thread (`I`)
+    Idle,
+    /// Any other state code
+    Other(char),
+}
+
+impl From<char> for ProcessState {
+    fn from(code: char) -> Self {
+        match code {
+            'R' => ProcessState::Running,
+            'S' => ProcessState::Sleeping,
+            'D' => ProcessState::DiskSleep,
+            'Z' => ProcessState::Zombie,
+            'T' => ProcessState::Stopped,
+            't' => ProcessState::TracingStop,
+            'X' | 'x' => ProcessState::Dead,
+            'I' => ProcessState::Idle,
+            code => ProcessState::Other(code),
+        }
+    }
+}
+
+/// Runtime statistics of a running [`Process`], collected from `/proc`
+///
+/// [`Process`]: crate::Process
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProcessStats {
+    /// Scheduling state of the process
+    pub state: ProcessState,
+    /// Time spent running in user mode
+    pub user_time: Duration,
+    /// Time spent running in kernel mode
+    pub system_time: Duration,
+    /// Resident set size in bytes
+    pub rss_bytes: u64,
+    /// Peak resident set size in bytes
+    pub peak_rss_bytes: u64,
+    /// Virtual memory size in bytes
+    pub vsz_bytes: u64,
+    /// Number of threads
+    pub threads: u64,
+    /// Number of voluntary context switches
+    pub voluntary_context_switches: u64,
+    /// Number of involuntary context switches
+    pub involuntary_context_switches: u64,
+    /// Number of open file descriptors, [`None`] if `/proc/<pid>/fd` is not
+    /// accessible to the caller
+    pub open_fds: Option<u64>,
+    /// Bytes read from storage, [`None`] if `/proc/<pid>/io` is not
+    /// accessible to the caller
+    pub read_bytes: Option<u64>,
+    /// Bytes written to storage, [`None`] if `/proc/<pid>/io` is not
+    /// accessible to the caller
+    pub write_bytes: Option<u64>,
+    /// Time at which the process started
+    pub start_time: SystemTime,
+}
+
+/// Fields of `/proc/<pid>/stat` used by [`ProcessStats`]
+#[derive(Debug, PartialEq, Eq)]
+struct Stat {
+    state: char,
+    utime: u64,
+    stime: u64,
+    num_threads: u64,
+    starttime: u64,
+    vsize: u64,
+    rss: u64,
+}
+
+/// Fields of `/proc/<pid>/status` used by [`ProcessStats`]
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Status {
+    vm_hwm_kb: u64,
+    vm_rss_kb: u64,
+    voluntary_ctxt_switches: u64,
+    nonvoluntary_ctxt_switches: u64,
+}
+
+/// Fields of `/proc/<pid>/io` used by [`ProcessStats`]
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Io {
+    read_bytes: u64,
+    write_bytes: u64,
+}
+
+/// Collects [`ProcessStats`] for `pid`
+pub(crate) fn read(pid: u32) -> Result<ProcessStats> {
+    let proc_dir = format!("/proc/{pid}");
+    let read_file = |name: &str| {
+        fs::read_to_string(format!("{proc_dir}/{name}")).map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => Error::NoSuchProcess { pid },
+            _ => Error::Io(err),
+        })
+    };
+
+    let stat = parse_stat(&read_file("stat")?).ok_or_else(|| invalid_data("stat"))?;
+    let status = parse_status(&read_file("status")?);
+    let io = read_file("io").ok().map(|io| parse_io(&io));
+    let open_fds = fs::read_dir(format!("{proc_dir}/fd"))
+        .ok()
+        .map(|entries| entries.count() as u64);
+
+    let ticks = sysconf(libc::_SC_CLK_TCK)?;
+    let page_size = sysconf(libc::_SC_PAGESIZE)?;
+
+    Ok(ProcessStats {
+        state: ProcessState::from(stat.state),
+        user_time: from_ticks(stat.utime, ticks),
+        system_time: from_ticks(stat.stime, ticks),
+        rss_bytes: stat.rss * page_size,
+        peak_rss_bytes: status.vm_hwm_kb.max(status.vm_rss_kb) * 1024,
+        vsz_bytes: stat.vsize,
+        threads: stat.num_threads,
+        voluntary_context_switches: status.voluntary_ctxt_switches,
+        involuntary_context_switches: status.nonvoluntary_ctxt_switches,
+        open_fds,
+        read_bytes: io.as_ref().map(|io| io.read_bytes),
+        write_bytes: io.as_ref().map(|io| io.write_bytes),
+        start_time: boot_time()? + from_ticks(stat.starttime, ticks),
+    })
+}
+
+/// Parses the contents of `/proc/<pid>/stat`
+fn parse_stat(contents: &str) -> Option<Stat> {
+    // The command name is wrapped in parentheses and may contain spaces or
+    // parentheses itself, so fields are located from the last `)`
+    let (_, fields) = contents.rsplit_once(')')?;
+    let fields = fields.split_whitespace().collect::<Vec<_>>();
+    // Offsets are relative to the `state` field, the third one in proc(5)
+    let field = |index: usize| fields.get(index)?.parse::<u64>().ok();
+
+    Some(Stat {
+        state: fields.first()?.chars().next()?,
+        utime: field(11)?,
+        stime: field(12)?,
+        num_threads: field(17)?,
+        starttime: field(19)?,
+        vsize: field(20)?,
+        rss: field(21)?,
+    })
+}
+
+/// Parses the contents of `/proc/<pid>/status`
+fn parse_status(contents: &str) -> Status {
+    let mut status = Status::default();
+
+    for line in contents.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value
+            .split_whitespace()
+            .next()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0);
+
+        match key {
+            "VmHWM" => status.vm_hwm_kb = value,
+            "VmRSS" => status.vm_rss_kb = value,
+            "voluntary_ctxt_switches" => status.voluntary_ctxt_switches = value,
+            "nonvoluntary_ctxt_switches" => status.nonvoluntary_ctxt_switches = value,
+            _ => {}
+        }
+    }
+
+    status
+}
+
+/// Parses the contents of `/proc/<pid>/io`
+fn parse_io(contents: &str) -> Io {
+    let mut io = Io::default();
+
+    for line in contents.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim().parse().unwrap_or(0);
+
+        match key {
+            "read_bytes" => io.read_bytes = value,
+            "write_bytes" => io.write_bytes = value,
+            _ => {}
+        }
+    }
+
+    io
+}
+
+/// Retrieves the time at which the system booted from `/proc/stat`
+fn boot_time() -> Result<SystemTime> {
+    let contents = fs::read_to_string("/proc/stat")?;
+    let btime = contents
+        .lines()
+        .find_map(|line| line.strip_prefix("btime "))
+        .and_then(|btime| btime.trim().parse().ok())
+        .ok_or_else(|| invalid_data("/proc/stat"))?;
+
+    Ok(UNIX_EPOCH + Duration::from_secs(btime))
+}
+
+/// Converts `value` clock ticks into a duration without overflowing
+fn from_ticks(value: u64, ticks: u64) -> Duration {
+    Duration::from_secs(value / ticks) + Duration::from_nanos(value % ticks * 1_000_000_000 / ticks)
+}
+
+fn sysconf(name: libc::c_int) -> Result<u64> {
+    match unsafe { libc::sysconf(name) } {
+        value if value > 0 => Ok(value as u64),
+        _ => Err(Error::Io(io::Error::last_os_error())),
+    }
+}
+
+fn invalid_data(file: &str) -> Error {
+    Error::Io(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Unexpected format of {file}"),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_stat_with_odd_command_name() {
+        let contents = "42 (we(ir) d) S 1 42 42 0 -1 4194304 82 0 0 0 7 3 0 0 20 0 2 0 73370 2703360 306 18446744073709551615 0";
+        let stat = parse_stat(contents).expect("Failed to parse stat");
+        assert_eq!(
+            stat,
+            Stat {
+                state: 'S',
+                utime: 7,
+                stime: 3,
+                num_threads: 2,
+                starttime: 73370,
+                vsize: 2703360,
+                rss: 306,
+            }
+        );
+        assert_eq!(parse_stat("42 (truncated) S 1"), None);
+    }
+
+    #[test]
+    fn parses_status_and_io() {
+        let status = parse_status(
+            "Name:\tcat\nVmHWM:\t    1792 kB\nVmRSS:\t    1024 kB\nvoluntary_ctxt_switches:\t3\nnonvoluntary_ctxt_switches:\t4\n",
+        );
+        assert_eq!(
+            status,
+            Status {
+                vm_hwm_kb: 1792,
+                vm_rss_kb: 1024,
+                voluntary_ctxt_switches: 3,
+                nonvoluntary_ctxt_switches: 4,
+            }
+        );
+
+        let io = parse_io("rchar: 3980\nread_bytes: 4096\nwrite_bytes: 8192\n");
+        assert_eq!(
+            io,
+            Io {
+                read_bytes: 4096,
+                write_bytes: 8192,
+            }
+        );
+    }
+
+    #[test]
+    fn maps_state_codes() {
+        assert_eq!(ProcessState::from('R'), ProcessState::Running);
+        assert_eq!(ProcessState::from('Z'), ProcessState::Zombie);
+        assert_eq!(ProcessState::from('W'), ProcessState::Other('W'));
+    }
+
+    #[test]
+    fn converts_large_tick_counts() {
+        assert_eq!(from_ticks(250, 100), Duration::from_millis(2500));
+        // About 6 years of uptime at 100 Hz overflows `ticks * 10^9`
+        assert_eq!(
+            from_ticks(20_000_000_000, 100),
+            Duration::from_secs(200_000_000)
+        );
+    }
+}
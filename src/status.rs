@@ -0,0 +1,155 @@
+use std::fmt;
+use std::os::unix::process::ExitStatusExt;
+
+use crate::Signal;
+
+/// Describes how a [`Process`] ended
+///
+/// [`Process`]: crate::Process
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ExitStatus {
+    /// The process exited normally with the provided exit code
+    Exited(i32),
+    /// The process was terminated by a signal
+    Signaled {
+        /// Platform specific number of the signal
+        signal: i32,
+        /// Whether the process produced a core dump
+        core_dumped: bool,
+    },
+    /// The process exited but its status could not be collected because it
+    /// is not a child of the current process
+    ///
+    /// See [`Process::from_pid`].
+    ///
+    /// [`Process::from_pid`]: crate::Process::from_pid
+    Unavailable,
+}
+
+impl ExitStatus {
+    /// Builds an [`ExitStatus`] from the raw status reported by `waitpid(2)`
+    pub(crate) fn from_raw(status: i32) -> Self {
+        if libc::WIFSIGNALED(status) {
+            return ExitStatus::Signaled {
+                signal: libc::WTERMSIG(status),
+                core_dumped: libc::WCOREDUMP(status),
+            };
+        }
+
+        ExitStatus::Exited(libc::WEXITSTATUS(status))
+    }
+
+    /// Returns `true` if the process exited normally with code `0`
+    pub fn success(&self) -> bool {
+        matches!(self, ExitStatus::Exited(0))
+    }
+
+    /// Retrieves the exit code of a process which exited normally
+    pub fn code(&self) -> Option<i32> {
+        match self {
+            ExitStatus::Exited(code) => Some(*code),
+            ExitStatus::Signaled { .. } | ExitStatus::Unavailable => None,
+        }
+    }
+
+    /// Retrieves the number of the signal which terminated the process
+    pub fn signal(&self) -> Option<i32> {
+        match self {
+            ExitStatus::Signaled { signal, .. } => Some(*signal),
+            ExitStatus::Exited(_) | ExitStatus::Unavailable => None,
+        }
+    }
+
+    /// Retrieves the name of the signal which terminated the process, e.g.
+    /// `SIGKILL`
+    ///
+    /// Returns [`None`] if the process exited normally or the signal is not
+    /// one of the standard signals covered by [`Signal`].
+    pub fn signal_name(&self) -> Option<&'static str> {
+        self.signal().and_then(Signal::from_raw).map(Signal::name)
+    }
+
+    /// Returns `true` if the process was terminated by a signal and produced
+    /// a core dump
+    pub fn core_dumped(&self) -> bool {
+        matches!(
+            self,
+            ExitStatus::Signaled {
+                core_dumped: true,
+                ..
+            }
+        )
+    }
+}
+
+impl From<std::process::ExitStatus> for ExitStatus {
+    fn from(status: std::process::ExitStatus) -> Self {
+        Self::from_raw(status.into_raw())
+    }
+}
+
+impl fmt::Display for ExitStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExitStatus::Exited(code) => write!(f, "exit code: {code}"),
+            ExitStatus::Signaled {
+                signal,
+                core_dumped,
+            } => {
+                write!(f, "signal: {signal}")?;
+
+                if let Some(name) = self.signal_name() {
+                    write!(f, " ({name})")?;
+                }
+
+                if *core_dumped {
+                    write!(f, " (core dumped)")?;
+                }
+
+                Ok(())
+            }
+            ExitStatus::Unavailable => write!(f, "exit status unavailable"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_exit_code() {
+        let status = ExitStatus::from(std::process::ExitStatus::from_raw(3 << 8));
+        assert_eq!(status, ExitStatus::Exited(3));
+        assert_eq!(status.code(), Some(3));
+        assert!(!status.success());
+        assert_eq!(status.to_string(), "exit code: 3");
+    }
+
+    #[test]
+    fn decodes_signal() {
+        let status = ExitStatus::from(std::process::ExitStatus::from_raw(libc::SIGKILL));
+        assert_eq!(status.signal(), Some(libc::SIGKILL));
+        assert_eq!(status.signal_name(), Some("SIGKILL"));
+        assert_eq!(status.code(), None);
+        assert!(!status.core_dumped());
+        assert_eq!(status.to_string(), "signal: 9 (SIGKILL)");
+    }
+
+    #[test]
+    fn decodes_core_dump() {
+        let status = ExitStatus::from(std::process::ExitStatus::from_raw(libc::SIGSEGV | 0x80));
+        assert!(status.core_dumped());
+        assert_eq!(status.signal_name(), Some("SIGSEGV"));
+        assert!(status.to_string().ends_with("(core dumped)"));
+    }
+
+    #[test]
+    fn unavailable_status() {
+        let status = ExitStatus::Unavailable;
+        assert!(!status.success());
+        assert_eq!(status.code(), None);
+        assert_eq!(status.signal(), None);
+        assert_eq!(status.to_string(), "exit status unavailable");
+    }
+}
@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use crate::Signal;
+
+/// Grace period used by [`StopPolicy::default`]
+const DEFAULT_GRACE_PERIOD: Duration = Duration::from_secs(10);
+
+/// A signal delivered by [`Process::stop`] and how long to wait for the
+/// process to exit afterwards
+///
+/// [`Process::stop`]: crate::Process::stop
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StopStep {
+    pub signal: Signal,
+    pub timeout: Duration,
+}
+
+/// Describes how [`Process::stop`] shuts a process down
+///
+/// Each step delivers a signal and waits for the process to exit. If the
+/// process is still alive once every step has been tried, it is killed with
+/// [`Signal::Kill`].
+///
+/// The default policy sends [`Signal::Term`] to the process group and waits
+/// up to 10 seconds before escalating.
+///
+/// # Example
+///
+/// ```ignore
+/// use std::time::Duration;
+///
+/// use xprocess::{Signal, StopPolicy};
+///
+/// let policy = StopPolicy::new()
+///     .then(Signal::Int, Duration::from_secs(2))
+///     .then(Signal::Term, Duration::from_secs(5));
+/// ```
+///
+/// [`Process::stop`]: crate::Process::stop
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StopPolicy {
+    steps: Vec<StopStep>,
+    group: bool,
+}
+
+impl StopPolicy {
+    /// Creates a policy without steps, which kills the process group right
+    /// away
+    pub fn new() -> Self {
+        Self {
+            steps: Vec::new(),
+            group: true,
+        }
+    }
+
+    /// Appends a step delivering `signal` and waiting up to `timeout` for the
+    /// process to exit
+    pub fn then(mut self, signal: Signal, timeout: Duration) -> Self {
+        self.steps.push(StopStep { signal, timeout });
+        self
+    }
+
+    /// Whether signals are delivered to the whole process group (default) or
+    /// only to the process itself
+    pub fn group(mut self, group: bool) -> Self {
+        self.group = group;
+        self
+    }
+
+    /// Retrieves the steps of this policy in delivery order
+    pub fn steps(&self) -> &[StopStep] {
+        &self.steps
+    }
+
+    /// Returns `true` if signals are delivered to the whole process group
+    pub fn is_group(&self) -> bool {
+        self.group
+    }
+}
+
+impl Default for StopPolicy {
+    fn default() -> Self {
+        Self::new().then(Signal::Term, DEFAULT_GRACE_PERIOD)
+    }
+}
@@ -0,0 +1,305 @@
+use std::ffi::{CStr, CString};
+use std::io;
+use std::{mem, ptr};
+
+use crate::{Error, Result};
+
+/// Type of the group list filled by `getgrouplist(3)`, which differs between
+/// C libraries
+#[cfg(target_vendor = "apple")]
+type RawGroup = libc::c_int;
+#[cfg(not(target_vendor = "apple"))]
+type RawGroup = libc::gid_t;
+
+/// Upper bound on the size of the group list, in case `getgrouplist(3)`
+/// never reports the number of groups
+const MAX_GROUPS: usize = 65536;
+
+/// User the program runs as, see [`ProcessBuilder::user`]
+///
+/// [`ProcessBuilder::user`]: crate::ProcessBuilder::user
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum User {
+    /// User ID, which does not need an entry in the user database
+    Id(u32),
+    /// User name, resolved with `getpwnam(3)` when spawning
+    Name(String),
+}
+
+impl From<u32> for User {
+    fn from(uid: u32) -> Self {
+        User::Id(uid)
+    }
+}
+
+impl From<&str> for User {
+    fn from(name: &str) -> Self {
+        User::Name(name.to_owned())
+    }
+}
+
+impl From<String> for User {
+    fn from(name: String) -> Self {
+        User::Name(name)
+    }
+}
+
+/// Group the program runs as, see [`ProcessBuilder::group`]
+///
+/// [`ProcessBuilder::group`]: crate::ProcessBuilder::group
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Group {
+    /// Group ID, which does not need an entry in the group database
+    Id(u32),
+    /// Group name, resolved with `getgrnam(3)` when spawning
+    Name(String),
+}
+
+impl From<u32> for Group {
+    fn from(gid: u32) -> Self {
+        Group::Id(gid)
+    }
+}
+
+impl From<&str> for Group {
+    fn from(name: &str) -> Self {
+        Group::Name(name.to_owned())
+    }
+}
+
+impl From<String> for Group {
+    fn from(name: String) -> Self {
+        Group::Name(name)
+    }
+}
+
+/// Resolved identity the child switches to before `exec`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Credentials {
+    uid: Option<libc::uid_t>,
+    gid: Option<libc::gid_t>,
+    groups: Option<Vec<libc::gid_t>>,
+}
+
+impl Credentials {
+    /// Resolves names into IDs
+    ///
+    /// Unless provided, the primary group of `user` and the groups it is a
+    /// member of are looked up in the user database. A user ID without an
+    /// entry there requires an explicit group. When running as root,
+    /// supplementary groups are always replaced once the user changes so
+    /// the program can not keep the groups of the caller.
+    pub(crate) fn resolve(
+        user: Option<&User>,
+        group: Option<&Group>,
+        groups: Option<&[Group]>,
+    ) -> Result<Self> {
+        let passwd = match user {
+            Some(User::Id(uid)) => lookup_passwd(|pwd, buf, len, result| unsafe {
+                libc::getpwuid_r(*uid, pwd, buf, len, result)
+            })?,
+            Some(User::Name(name)) => {
+                let unknown = || Error::UnknownUser { name: name.clone() };
+                let c_name = CString::new(name.as_str()).map_err(|_| unknown())?;
+                let passwd = lookup_passwd(|pwd, buf, len, result| unsafe {
+                    libc::getpwnam_r(c_name.as_ptr(), pwd, buf, len, result)
+                })?;
+                Some(passwd.ok_or_else(unknown)?)
+            }
+            None => None,
+        };
+
+        let uid = match user {
+            Some(User::Id(uid)) => Some(*uid),
+            _ => passwd.as_ref().map(|passwd| passwd.uid),
+        };
+        let gid = match group {
+            Some(group) => Some(resolve_group(group)?),
+            None => passwd.as_ref().map(|passwd| passwd.gid),
+        };
+
+        // Keeping the group of the caller would retain its privileges
+        if let Some(uid) = uid
+            && gid.is_none()
+        {
+            return Err(Error::UnknownUser {
+                name: uid.to_string(),
+            });
+        }
+        let groups = match groups {
+            Some(groups) => Some(groups.iter().map(resolve_group).collect::<Result<_>>()?),
+            None if uid.is_some() && unsafe { libc::geteuid() } == 0 => Some(match passwd {
+                Some(ref passwd) => group_list(&passwd.name, gid.unwrap_or(passwd.gid)),
+                None => Vec::new(),
+            }),
+            None => None,
+        };
+
+        Ok(Self { uid, gid, groups })
+    }
+
+    /// Switches the calling process to these credentials
+    ///
+    /// Groups are changed first as doing so requires the privileges given
+    /// up by `setuid(2)`. Only calls async-signal-safe functions, so it is
+    /// safe to use between `fork` and `exec`.
+    pub(crate) fn apply(&self) -> io::Result<()> {
+        if let Some(ref groups) = self.groups
+            && unsafe { libc::setgroups(groups.len() as _, groups.as_ptr()) } < 0
+        {
+            return Err(io::Error::last_os_error());
+        }
+
+        if let Some(gid) = self.gid
+            && unsafe { libc::setgid(gid) } < 0
+        {
+            return Err(io::Error::last_os_error());
+        }
+
+        if let Some(uid) = self.uid
+            && unsafe { libc::setuid(uid) } < 0
+        {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+}
+
+/// Entry of the user database used by [`Credentials::resolve`]
+struct Passwd {
+    uid: libc::uid_t,
+    gid: libc::gid_t,
+    name: CString,
+}
+
+/// Calls `getpwnam_r(3)` or `getpwuid_r(3)` through `lookup`, growing the
+/// buffer until the entry fits
+fn lookup_passwd<F>(lookup: F) -> Result<Option<Passwd>>
+where
+    F: Fn(*mut libc::passwd, *mut libc::c_char, usize, *mut *mut libc::passwd) -> libc::c_int,
+{
+    let mut buf = vec![0; 1024];
+
+    loop {
+        let mut pwd: libc::passwd = unsafe { mem::zeroed() };
+        let mut result = ptr::null_mut();
+
+        match lookup(&mut pwd, buf.as_mut_ptr(), buf.len(), &mut result) {
+            libc::ERANGE => buf.resize(buf.len() * 2, 0),
+            _ if !result.is_null() => {
+                return Ok(Some(Passwd {
+                    uid: pwd.pw_uid,
+                    gid: pwd.pw_gid,
+                    name: unsafe { CStr::from_ptr(pwd.pw_name) }.to_owned(),
+                }));
+            }
+            // Missing entries are reported with any of these by some libcs
+            0 | libc::ENOENT | libc::ESRCH => return Ok(None),
+            errno => return Err(io::Error::from_raw_os_error(errno).into()),
+        }
+    }
+}
+
+/// Resolves `group` into a group ID with `getgrnam_r(3)`
+fn resolve_group(group: &Group) -> Result<libc::gid_t> {
+    let name = match group {
+        Group::Id(gid) => return Ok(*gid),
+        Group::Name(name) => name,
+    };
+    let unknown = || Error::UnknownGroup { name: name.clone() };
+    let c_name = CString::new(name.as_str()).map_err(|_| unknown())?;
+    let mut buf = vec![0; 1024];
+
+    loop {
+        let mut grp: libc::group = unsafe { mem::zeroed() };
+        let mut result = ptr::null_mut();
+
+        match unsafe {
+            libc::getgrnam_r(
+                c_name.as_ptr(),
+                &mut grp,
+                buf.as_mut_ptr(),
+                buf.len(),
+                &mut result,
+            )
+        } {
+            libc::ERANGE => buf.resize(buf.len() * 2, 0),
+            _ if !result.is_null() => return Ok(grp.gr_gid),
+            0 | libc::ENOENT | libc::ESRCH => return Err(unknown()),
+            errno => return Err(io::Error::from_raw_os_error(errno).into()),
+        }
+    }
+}
+
+/// Lists the groups `user` is a member of, including `gid`, with
+/// `getgrouplist(3)`
+fn group_list(user: &CStr, gid: libc::gid_t) -> Vec<libc::gid_t> {
+    let mut groups: Vec<RawGroup> = vec![0; 32];
+
+    loop {
+        let mut count = groups.len() as libc::c_int;
+        let result =
+            unsafe { libc::getgrouplist(user.as_ptr(), gid as _, groups.as_mut_ptr(), &mut count) };
+
+        if result >= 0 || groups.len() >= MAX_GROUPS {
+            groups.truncate((count.max(0) as usize).min(groups.len()));
+            return groups
+                .into_iter()
+                .map(|group| group as libc::gid_t)
+                .collect();
+        }
+
+        // glibc reports the required size in `count`, others do not
+        let len = (count.max(0) as usize).max(groups.len() * 2);
+        groups.resize(len.min(MAX_GROUPS), 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_root() {
+        let credentials = Credentials::resolve(Some(&User::from("root")), None, None)
+            .expect("Failed to resolve root");
+        assert_eq!(credentials.uid, Some(0));
+        assert_eq!(credentials.gid, Some(0));
+
+        let credentials = Credentials::resolve(None, Some(&Group::Id(42)), Some(&[]))
+            .expect("Failed to resolve IDs");
+        assert_eq!(
+            credentials,
+            Credentials {
+                uid: None,
+                gid: Some(42),
+                groups: Some(Vec::new()),
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_names() {
+        let err = Credentials::resolve(Some(&User::from("xprocess-missing-user")), None, None)
+            .expect_err("User should not exist");
+        assert!(matches!(err, Error::UnknownUser { ref name } if name == "xprocess-missing-user"));
+
+        let err = Credentials::resolve(None, Some(&Group::from("xprocess-missing-group")), None)
+            .expect_err("Group should not exist");
+        assert!(matches!(err, Error::UnknownGroup { .. }));
+    }
+
+    #[test]
+    fn requires_group_of_unknown_user_id() {
+        let err = Credentials::resolve(Some(&User::Id(54321)), None, None)
+            .expect_err("User ID should not exist");
+        assert!(matches!(err, Error::UnknownUser { ref name } if name == "54321"));
+
+        let credentials =
+            Credentials::resolve(Some(&User::Id(54321)), Some(&Group::Id(54321)), None)
+                .expect("Failed to resolve IDs");
+        assert_eq!(credentials.uid, Some(54321));
+        assert_eq!(credentials.gid, Some(54321));
+    }
+}